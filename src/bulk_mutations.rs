@@ -1,10 +1,34 @@
+use std::{collections::HashMap, io, time::Duration};
+
+use futures::{future::Either, stream, Stream, TryStreamExt};
 use serde::Deserialize;
+use serde_json::{Map, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, Lines};
+use tokio_util::io::StreamReader;
+
+use crate::{
+    collections::Collection,
+    common::{resource, Id},
+    metafields::Metafield,
+    products::{
+        media::Media,
+        product::{Product, ProductQueryBuilder},
+        product_variant::{ProductVariant, ProductVariantQueryBuilder},
+    },
+    staged_uploads::{stage_upload, StagedUploadInput, StagedUploadTargetGenerateUploadResource},
+    utils::{
+        mutation_payload, run_request, GraphQlError, GraphQlRequest, ResponseTypes, ShopifyClient,
+        ShopifyGqlError, ShopifyResult, UserError,
+    },
+};
 
-use crate::{common::Id, utils::ResponseTypes};
+/// The fields selected whenever a `BulkOperation` is fetched.
+const BULK_OPERATION_FIELDS: &str = "id status errorCode objectCount url";
 
-#[derive(Debug, Deserialize)]
+/// The status of a bulk operation.
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
 #[allow(clippy::upper_case_acronyms)]
-enum BulkOperationStatus {
+pub enum BulkOperationStatus {
     CANCELED,
     CANCELING,
     COMPLETED,
@@ -14,40 +38,910 @@ enum BulkOperationStatus {
     RUNNING,
 }
 
-#[allow(unused)]
-#[derive(Debug, Deserialize)]
+impl BulkOperationStatus {
+    /// Returns `true` once the operation has stopped, whether or not it
+    /// succeeded.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            BulkOperationStatus::CANCELED
+                | BulkOperationStatus::COMPLETED
+                | BulkOperationStatus::EXPIRED
+                | BulkOperationStatus::FAILED
+        )
+    }
+}
+
+/// Why a bulk operation failed.
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum BulkOperationErrorCode {
+    ACCESS_DENIED,
+    INTERNAL_SERVER_ERROR,
+    TIMEOUT,
+}
+
+/// The kind of bulk operation, used to look up the one that is running.
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum BulkOperationType {
+    MUTATION,
+    QUERY,
+}
+
+/// An asynchronous bulk query or mutation.
+#[derive(Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct BulkOperation {
-    id: Id,
+pub struct BulkOperation {
+    id: Id<resource::BulkOperation>,
     status: BulkOperationStatus,
+    error_code: Option<BulkOperationErrorCode>,
+
+    /// An `UnsignedInt64`, which Shopify sends as a string.
+    object_count: Option<String>,
+
     url: Option<String>,
 }
 
-#[allow(unused)]
-struct BulkOperationBuilder {
-    id: Id,
-    status: BulkOperationStatus,
-    url: Option<String>,
+impl BulkOperation {
+    pub fn id(&self) -> &Id<resource::BulkOperation> {
+        &self.id
+    }
+
+    pub fn status(&self) -> BulkOperationStatus {
+        self.status
+    }
+
+    pub fn error_code(&self) -> Option<BulkOperationErrorCode> {
+        self.error_code
+    }
+
+    /// The number of objects processed so far.
+    pub fn object_count(&self) -> Option<u64> {
+        self.object_count.as_ref()?.parse().ok()
+    }
+
+    /// The URL of the results file, once the operation has completed.
+    ///
+    /// This is `None` if the operation completed without any results.
+    pub fn url(&self) -> Option<&String> {
+        self.url.as_ref()
+    }
+
+    /// Downloads the results of a completed bulk query into memory.
+    ///
+    /// Use [`reader`](Self::reader) instead for exports that may be too large
+    /// to hold at once.
+    pub async fn records(&self, client: &ShopifyClient) -> ShopifyResult<Vec<BulkRecord>> {
+        self.reader(client).await?.into_stream().try_collect().await
+    }
+
+    /// Starts downloading the results of a completed bulk query and returns a
+    /// reader that parses them as they arrive.
+    pub async fn reader(
+        &self,
+        client: &ShopifyClient,
+    ) -> ShopifyResult<BulkResultReader<impl AsyncBufRead + Unpin>> {
+        Ok(BulkResultReader::new(self.download(client).await?))
+    }
+
+    /// Downloads and parses the results of a completed bulk mutation.
+    pub async fn mutation_results(
+        &self,
+        client: &ShopifyClient,
+    ) -> ShopifyResult<BulkMutationReport> {
+        BulkMutationReport::from_reader(self.download(client).await?).await
+    }
+
+    async fn download(&self, client: &ShopifyClient) -> ShopifyResult<impl AsyncBufRead + Unpin> {
+        let body = match &self.url {
+            Some(url) => {
+                let res = client.http().get(url).send().await?.error_for_status()?;
+                let body = res.bytes_stream().map_err(io::Error::other);
+
+                Either::Left(body)
+            }
+
+            // Nothing matched the query, so there is no file to download.
+            None => Either::Right(stream::empty()),
+        };
+
+        Ok(StreamReader::new(body))
+    }
+}
+
+/// A single object from the results of a bulk query.
+///
+/// Collections, metafields and media only come from nested connections, so
+/// the reader attaches them to their product or variant rather than
+/// returning them on their own.
+#[derive(Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum BulkRecord {
+    Product(Product),
+    ProductVariant(ProductVariant),
+    Collection(Collection),
+    Metafield(Metafield),
+    Media(Media),
+}
+
+impl BulkRecord {
+    pub fn id(&self) -> Id {
+        match self {
+            BulkRecord::Product(p) => p.id().clone().into_any(),
+            BulkRecord::ProductVariant(v) => v.id().clone().into_any(),
+            BulkRecord::Collection(c) => c.id().clone().into_any(),
+            BulkRecord::Metafield(m) => m.id().clone().into_any(),
+            BulkRecord::Media(m) => m.id().clone(),
+        }
+    }
+
+    /// The global ID of the record, without cloning it into an [`Id`].
+    fn gid(&self) -> &String {
+        match self {
+            BulkRecord::Product(p) => p.id().inner(),
+            BulkRecord::ProductVariant(v) => v.id().inner(),
+            BulkRecord::Collection(c) => c.id().inner(),
+            BulkRecord::Metafield(m) => m.id().inner(),
+            BulkRecord::Media(m) => m.id().inner(),
+        }
+    }
+
+    /// Attaches `child` to this record, or to one of its variants, if
+    /// `parent_id` refers to it, otherwise hands `child` back.
+    fn attach(&mut self, child: BulkRecord, parent_id: &Id) -> Option<BulkRecord> {
+        if self.gid() != parent_id.inner() {
+            return match (self, child) {
+                (BulkRecord::Product(p), BulkRecord::Metafield(m)) => {
+                    match p.variant_mut(parent_id) {
+                        Some(v) => {
+                            v.push_metafield(m);
+                            None
+                        }
+                        None => Some(BulkRecord::Metafield(m)),
+                    }
+                }
+
+                (_, child) => Some(child),
+            };
+        }
+
+        match (self, child) {
+            (BulkRecord::Product(p), BulkRecord::ProductVariant(v)) => p.push_variant(v),
+            (BulkRecord::Product(p), BulkRecord::Collection(c)) => p.push_collection(c),
+            (BulkRecord::Product(p), BulkRecord::Metafield(m)) => p.push_metafield(m),
+            (BulkRecord::Product(p), BulkRecord::Media(m)) => p.push_media(m),
+            (BulkRecord::ProductVariant(v), BulkRecord::Metafield(m)) => v.push_metafield(m),
+            (_, child) => return Some(child),
+        }
+
+        None
+    }
+
+    /// Parses a line of a bulk query's JSONL output, returning the record and
+    /// the ID of its parent, if it came from a nested connection.
+    pub(crate) fn from_line(line: &str) -> ShopifyResult<(Self, Option<Id>)> {
+        let parse_error =
+            |e: serde_json::Error| ShopifyGqlError::ResponseError(format!("{}: {}", e, line));
+
+        let mut value: Value = serde_json::from_str(line).map_err(parse_error)?;
+        let parent_id = match value.as_object_mut().and_then(|o| o.remove("__parentId")) {
+            Some(parent_id) => Some(serde_json::from_value(parent_id).map_err(parse_error)?),
+            None => None,
+        };
+
+        Ok((Self::from_value(value)?, parent_id))
+    }
+
+    /// Parses an object, telling what it is by the type in its ID.
+    fn from_value(value: Value) -> ShopifyResult<Self> {
+        let parse_error = |e: serde_json::Error| ShopifyGqlError::ResponseError(format!("{}", e));
+
+        let id = value.get("id").and_then(Value::as_str).unwrap_or_default();
+        let kind = id
+            .strip_prefix("gid://shopify/")
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or_default();
+
+        match kind {
+            "ProductVariant" => Ok(BulkRecord::ProductVariant(
+                serde_json::from_value(value).map_err(parse_error)?,
+            )),
+            "Product" => Ok(BulkRecord::Product(
+                serde_json::from_value(value).map_err(parse_error)?,
+            )),
+            "Collection" => Ok(BulkRecord::Collection(
+                serde_json::from_value(value).map_err(parse_error)?,
+            )),
+            "Metafield" => Ok(BulkRecord::Metafield(
+                serde_json::from_value(value).map_err(parse_error)?,
+            )),
+            "MediaImage" | "Video" | "ExternalVideo" | "Model3d" => Ok(BulkRecord::Media(
+                serde_json::from_value(value).map_err(parse_error)?,
+            )),
+            _ => Err(ShopifyGqlError::ResponseError(format!(
+                "Unsupported bulk operation record: {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Reads the JSONL results of a bulk query one line at a time.
+///
+/// In the results file, the nodes of nested connections are written as
+/// separate lines, tagged with the `__parentId` of the object they belong to.
+/// The reader attaches them back to their parent, so each record has the same
+/// nested shape that a regular query would return.
+///
+/// Shopify writes an object's children after it and before the next top-level
+/// object, so only one top-level record is held in memory at a time.
+pub struct BulkResultReader<R> {
+    lines: Lines<R>,
+
+    /// The top-level record whose children are still being read.
+    pending: Option<BulkRecord>,
+}
+
+impl<R: AsyncBufRead + Unpin> BulkResultReader<R> {
+    /// Reads results from `reader`, such as a results file that was saved to
+    /// disk.
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            pending: None,
+        }
+    }
+
+    /// Returns the next top-level record along with its children, or `None`
+    /// once the results are exhausted.
+    pub async fn next_record(&mut self) -> ShopifyResult<Option<BulkRecord>> {
+        while let Some(line) = self.lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (record, parent_id) = BulkRecord::from_line(&line)?;
+            match (parent_id, self.pending.as_mut()) {
+                (None, _) => {
+                    if let Some(done) = self.pending.replace(record) {
+                        return Ok(Some(done));
+                    }
+                }
+
+                (Some(parent_id), Some(pending)) => {
+                    if let Some(record) = pending.attach(record, &parent_id) {
+                        return Err(ShopifyGqlError::ResponseError(format!(
+                            "Bulk operation record {} does not belong to {}",
+                            record.id().inner(),
+                            pending.id().inner()
+                        )));
+                    }
+                }
+
+                (Some(parent_id), None) => {
+                    return Err(ShopifyGqlError::ResponseError(format!(
+                        "Bulk operation record {} came before its parent {}",
+                        record.id().inner(),
+                        parent_id.inner()
+                    )));
+                }
+            }
+        }
+
+        Ok(self.pending.take())
+    }
+
+    /// Turns the reader into a stream of top-level records.
+    pub fn into_stream(self) -> impl Stream<Item = ShopifyResult<BulkRecord>> {
+        stream::try_unfold(self, |mut reader| async move {
+            Ok(reader.next_record().await?.map(|record| (record, reader)))
+        })
+    }
+}
+
+/// A query that can be run as a bulk operation.
+///
+/// Implemented for [`ProductQueryBuilder::products`] and
+/// [`ProductVariantQueryBuilder::product_variants`] queries.
+pub trait BulkQuery {
+    /// Renders the query without pagination arguments, so that it walks every
+    /// page of every connection.
+    fn bulk_query(&self) -> String;
+}
+
+impl BulkQuery for ProductQueryBuilder {
+    fn bulk_query(&self) -> String {
+        ProductQueryBuilder::bulk_query(self)
+    }
+}
+
+impl BulkQuery for ProductVariantQueryBuilder {
+    fn bulk_query(&self) -> String {
+        ProductVariantQueryBuilder::bulk_query(self)
+    }
+}
+
+/// The outcome of a single line of a bulk mutation's variables file.
+#[derive(Debug, PartialEq)]
+pub struct BulkMutationResult {
+    line_number: usize,
+    record: Option<BulkRecord>,
+    user_errors: Vec<UserError>,
+    errors: Vec<GraphQlError>,
+}
+
+impl BulkMutationResult {
+    /// The zero-based line of the variables file, which is also the position
+    /// of the input passed to [`BulkOperationBuilder::mutation`].
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// The object returned by the mutation, such as the updated variant.
+    pub fn record(&self) -> Option<&BulkRecord> {
+        self.record.as_ref()
+    }
+
+    pub fn user_errors(&self) -> &[UserError] {
+        &self.user_errors
+    }
+
+    /// Errors that stopped the mutation from running at all.
+    pub fn errors(&self) -> &[GraphQlError] {
+        &self.errors
+    }
+
+    /// Returns `true` if the mutation returned no errors of either kind.
+    pub fn is_success(&self) -> bool {
+        self.user_errors.is_empty() && self.errors.is_empty()
+    }
+
+    /// Parses a line of a bulk mutation's JSONL output, which holds the
+    /// response to one mutation along with its `__lineNumber`.
+    fn from_line(line: &str) -> ShopifyResult<Self> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Line {
+            data: Option<HashMap<String, Option<Map<String, Value>>>>,
+
+            #[serde(default)]
+            errors: Vec<GraphQlError>,
+
+            #[serde(rename = "__lineNumber")]
+            line_number: usize,
+        }
+
+        let parse_error =
+            |e: serde_json::Error| ShopifyGqlError::ResponseError(format!("{}: {}", e, line));
+
+        let line: Line = serde_json::from_str(line).map_err(parse_error)?;
+        let mut result = BulkMutationResult {
+            line_number: line.line_number,
+            record: None,
+            user_errors: vec![],
+            errors: line.errors,
+        };
+
+        // `data` holds the payload of the mutation, keyed by its name.
+        for payload in line
+            .data
+            .into_iter()
+            .flat_map(|d| d.into_values())
+            .flatten()
+        {
+            for (field, value) in payload {
+                match (field.as_str(), value) {
+                    (_, Value::Null) => {}
+
+                    ("userErrors", user_errors) => {
+                        result.user_errors =
+                            serde_json::from_value(user_errors).map_err(parse_error)?;
+                    }
+
+                    (_, value) => result.record = Some(BulkRecord::from_value(value)?),
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// The results of a bulk mutation, ordered by line number.
+#[derive(Debug, PartialEq, Default)]
+pub struct BulkMutationReport {
+    results: Vec<BulkMutationResult>,
+}
+
+impl BulkMutationReport {
+    /// Parses the JSONL results of a bulk mutation from `reader`, such as a
+    /// results file that was saved to disk.
+    pub async fn from_reader<R: AsyncBufRead + Unpin>(reader: R) -> ShopifyResult<Self> {
+        let mut lines = reader.lines();
+        let mut results = vec![];
+
+        while let Some(line) = lines.next_line().await? {
+            if !line.trim().is_empty() {
+                results.push(BulkMutationResult::from_line(&line)?);
+            }
+        }
+
+        // Shopify doesn't guarantee that lines are written in order.
+        results.sort_by_key(|r| r.line_number);
+
+        Ok(Self { results })
+    }
+
+    pub fn results(&self) -> &[BulkMutationResult] {
+        &self.results
+    }
+
+    /// Iterates over the mutations that returned errors.
+    pub fn failures(&self) -> impl Iterator<Item = &BulkMutationResult> {
+        self.results.iter().filter(|r| !r.is_success())
+    }
+
+    /// Returns `true` if every mutation succeeded.
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(BulkMutationResult::is_success)
+    }
+
+    pub fn into_results(self) -> Vec<BulkMutationResult> {
+        self.results
+    }
+}
+
+/// A mutation that can be run as a bulk operation.
+///
+/// Implemented for [`ProductVariantQueryBuilder::product_variant_update`]
+/// mutations.
+pub trait BulkMutation {
+    /// Renders the mutation document, which takes its input as `$input`.
+    fn bulk_mutation(&self) -> String;
+
+    /// The variables of this mutation, written as one line of the variables
+    /// file.
+    fn bulk_variables(&self) -> Value;
+}
+
+impl BulkMutation for ProductVariantQueryBuilder {
+    fn bulk_mutation(&self) -> String {
+        self.update_mutation()
+    }
+
+    fn bulk_variables(&self) -> Value {
+        self.update_variables()
+    }
+}
+
+#[derive(Debug)]
+enum BulkOperationKind {
+    Query(String),
+
+    Mutation {
+        mutation: String,
+
+        /// The JSONL variables file, with one line per mutation.
+        variables: String,
+    },
+}
+
+/// Starts a bulk operation and waits for it to finish.
+///
+/// ```no_run
+/// use shopify_gql::{bulk_mutations::BulkOperationBuilder, prelude::*};
+///
+/// # async fn run() -> ShopifyResult<()> {
+/// let client = ShopifyClient::from_env()?;
+/// let query = ProductQueryBuilder::products(ProductsConnection::First(250))
+///     .title()
+///     .variants(ProductVariantQueryBuilder::product_variants(ProductsConnection::First(250)).sku());
+///
+/// let operation = BulkOperationBuilder::query(query).build(&client).await?;
+/// let records = operation.records(&client).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BulkOperationBuilder {
+    kind: BulkOperationKind,
+    poll_interval: Duration,
 }
 
-#[allow(unused)]
 impl BulkOperationBuilder {
-    fn query(query_type: ResponseTypes) -> Self {
-        // TODO: Need to construct query, but remove the numbers in `first` or `last`
-        match query_type {
-            ResponseTypes::Product(_) => todo!(),
-            ResponseTypes::ProductVariant(_) => todo!(),
-            _ => unreachable!(),
+    /// Runs `query` as a `bulkOperationRunQuery`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `query` is not a `products` or `product_variants` query.
+    pub fn query(query: impl BulkQuery) -> Self {
+        Self {
+            kind: BulkOperationKind::Query(query.bulk_query()),
+            poll_interval: Duration::from_secs(2),
         }
+    }
+
+    /// Runs every mutation in `inputs` as a single `bulkOperationRunMutation`.
+    ///
+    /// The inputs are written to a JSONL file and uploaded with
+    /// `stagedUploadsCreate` before the operation is started. Once it has
+    /// finished, [`BulkOperation::mutation_results`] reports how each input
+    /// fared.
+    ///
+    /// Every input is run with the document of the first one, so they must all
+    /// select the same fields.
+    ///
+    /// ```no_run
+    /// use shopify_gql::{bulk_mutations::BulkOperationBuilder, prelude::*};
+    ///
+    /// # async fn run() -> ShopifyResult<()> {
+    /// let client = ShopifyClient::from_env()?;
+    /// let updates = vec![
+    ///     ProductVariantQueryBuilder::product_variant_update(Id::product_variant("1")?)
+    ///         .update_price("10.00".parse()?),
+    ///     ProductVariantQueryBuilder::product_variant_update(Id::product_variant("2")?)
+    ///         .update_price("12.50".parse()?),
+    /// ];
+    ///
+    /// let operation = BulkOperationBuilder::mutation(updates).build(&client).await?;
+    /// for failure in operation.mutation_results(&client).await?.failures() {
+    ///     println!("line {}: {:?}", failure.line_number(), failure.user_errors());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `inputs` is empty or the inputs render different documents.
+    pub fn mutation<M: BulkMutation>(inputs: impl IntoIterator<Item = M>) -> Self {
+        let mut inputs = inputs.into_iter().peekable();
+        let mutation = match inputs.peek() {
+            Some(first) => first.bulk_mutation(),
+            None => panic!("bulk mutations require at least one input"),
+        };
 
-        todo!()
+        let variables = inputs
+            .map(|input| {
+                assert_eq!(
+                    input.bulk_mutation(),
+                    mutation,
+                    "every input of a bulk mutation must select the same fields"
+                );
+
+                input.bulk_variables().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Self {
+            kind: BulkOperationKind::Mutation {
+                mutation,
+                variables,
+            },
+            poll_interval: Duration::from_secs(2),
+        }
     }
 
-    fn mutation() -> Self {
-        todo!()
+    /// How often to check whether the operation has finished. Defaults to two
+    /// seconds.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Starts the operation through `client` and waits until it has finished.
+    ///
+    /// Returns a [`ShopifyGqlError::BulkOperationFailed`] if the operation
+    /// did not complete.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<BulkOperation> {
+        let (operation, operation_type) = match self.kind {
+            BulkOperationKind::Query(query) => (
+                run_bulk_query(client, &query).await?,
+                BulkOperationType::QUERY,
+            ),
+
+            BulkOperationKind::Mutation {
+                mutation,
+                variables,
+            } => (
+                run_bulk_mutation(client, &mutation, variables).await?,
+                BulkOperationType::MUTATION,
+            ),
+        };
+
+        wait_for_completion(client, operation, operation_type, self.poll_interval).await
     }
+}
+
+async fn run_bulk_query(client: &ShopifyClient, query: &str) -> ShopifyResult<BulkOperation> {
+    let request = GraphQlRequest::new(format!(
+        "mutation bulkOperationRunQuery($query: String!) {{ bulkOperationRunQuery(query: $query) {{ bulkOperation {{ {} }} userErrors {{ field message }} }} }}",
+        BULK_OPERATION_FIELDS
+    ))
+    .with_variable("query", query);
 
-    fn build() -> BulkOperation {
-        todo!()
+    match run_request(client, &request).await?.into_data()? {
+        ResponseTypes::BulkOperationRunQuery {
+            bulk_operation,
+            user_errors,
+        } => mutation_payload(bulk_operation, user_errors),
+
+        res => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+    }
+}
+
+/// Uploads `variables` and starts a bulk operation that runs `mutation` once
+/// per line.
+async fn run_bulk_mutation(
+    client: &ShopifyClient,
+    mutation: &str,
+    variables: String,
+) -> ShopifyResult<BulkOperation> {
+    let upload = StagedUploadInput::new(
+        StagedUploadTargetGenerateUploadResource::BULK_MUTATION_VARIABLES,
+        "bulk_op_vars.jsonl",
+        "text/jsonl",
+    );
+    let target = stage_upload(client, upload, variables.into_bytes()).await?;
+
+    let path = target.parameter("key").ok_or_else(|| {
+        ShopifyGqlError::ResponseError("The staged upload target has no `key` parameter".into())
+    })?;
+
+    let request = GraphQlRequest::new(format!(
+        "mutation bulkOperationRunMutation($mutation: String!, $stagedUploadPath: String!) {{ bulkOperationRunMutation(mutation: $mutation, stagedUploadPath: $stagedUploadPath) {{ bulkOperation {{ {} }} userErrors {{ field message }} }} }}",
+        BULK_OPERATION_FIELDS
+    ))
+    .with_variable("mutation", mutation)
+    .with_variable("stagedUploadPath", path);
+
+    match run_request(client, &request).await?.into_data()? {
+        ResponseTypes::BulkOperationRunMutation {
+            bulk_operation,
+            user_errors,
+        } => mutation_payload(bulk_operation, user_errors),
+
+        res => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+    }
+}
+
+/// Polls `currentBulkOperation` until `operation` has finished.
+pub(crate) async fn wait_for_completion(
+    client: &ShopifyClient,
+    mut operation: BulkOperation,
+    operation_type: BulkOperationType,
+    poll_interval: Duration,
+) -> ShopifyResult<BulkOperation> {
+    while !operation.status.is_finished() {
+        tokio::time::sleep(poll_interval).await;
+
+        let request = GraphQlRequest::new(format!(
+            "query currentBulkOperation($type: BulkOperationType!) {{ currentBulkOperation(type: $type) {{ {} }} }}",
+            BULK_OPERATION_FIELDS
+        ))
+        .with_variable("type", format!("{:?}", operation_type));
+
+        let current = match run_request(client, &request).await?.into_data()? {
+            ResponseTypes::CurrentBulkOperation(Some(current)) => current,
+            res => return Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        };
+
+        if current.id != operation.id {
+            return Err(ShopifyGqlError::ResponseError(format!(
+                "Bulk operation {} was replaced by {}",
+                operation.id.inner(),
+                current.id.inner()
+            )));
+        }
+
+        operation = current;
+    }
+
+    match operation.status {
+        BulkOperationStatus::COMPLETED => Ok(operation),
+        _ => Err(ShopifyGqlError::BulkOperationFailed(operation)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{collections::CollectionQueryBuilder, products::ProductsConnection};
+
+    #[test]
+    fn strips_pagination_from_bulk_queries() {
+        let query = ProductQueryBuilder::products(ProductsConnection::First(10))
+            .search("vendor:TEST")
+            .variants(ProductVariantQueryBuilder::product_variants(
+                ProductsConnection::First(5),
+            ));
+
+        assert_eq!(
+            query.bulk_query(),
+            "{ products(query: \"vendor:TEST\") { edges { node { id\n,variants { edges { node { id } } } } } } }"
+        );
+    }
+
+    #[test]
+    fn walks_nested_connections_in_bulk_queries() {
+        let query = ProductQueryBuilder::products(ProductsConnection::First(10))
+            .collections(
+                CollectionQueryBuilder::collections(ProductsConnection::First(5))
+                    .search("title:Summer"),
+            )
+            .metafields(ProductsConnection::First(5), Some("custom"))
+            .media(ProductsConnection::First(5))
+            .bulk_query();
+
+        assert!(query.contains("collections(query: \"title:Summer\") { edges { node { id } } }"));
+        assert!(query.contains("metafields(namespace: \"custom\") { edges { node { "));
+        assert!(query.contains("media { edges { node { "));
+        assert!(!query.contains("first:"));
+        assert!(!query.contains("pageInfo"));
+    }
+
+    #[tokio::test]
+    async fn reattaches_collections_metafields_and_media() {
+        let jsonl = concat!(
+            r#"{"id":"gid://shopify/Product/1"}"#,
+            "\n",
+            r#"{"id":"gid://shopify/ProductVariant/11","__parentId":"gid://shopify/Product/1"}"#,
+            "\n",
+            r#"{"id":"gid://shopify/Collection/5","title":"Summer","__parentId":"gid://shopify/Product/1"}"#,
+            "\n",
+            r#"{"id":"gid://shopify/Metafield/7","namespace":"custom","key":"care","type":"single_line_text_field","value":"Wash cold","__parentId":"gid://shopify/Product/1"}"#,
+            "\n",
+            r##"{"id":"gid://shopify/Metafield/8","namespace":"custom","key":"color","type":"color","value":"#ff0000","__parentId":"gid://shopify/ProductVariant/11"}"##,
+            "\n",
+            r#"{"id":"gid://shopify/MediaImage/9","alt":null,"mediaContentType":"IMAGE","status":"READY","preview":null,"__parentId":"gid://shopify/Product/1"}"#,
+            "\n",
+        );
+
+        let records: Vec<_> = BulkResultReader::new(jsonl.as_bytes())
+            .into_stream()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(records.len(), 1);
+        match &records[0] {
+            BulkRecord::Product(p) => {
+                assert_eq!(
+                    p.collections().unwrap().get_node(0).title(),
+                    Some(&"Summer".into())
+                );
+                assert_eq!(p.metafield("custom", "care").unwrap().value(), "Wash cold");
+                assert_eq!(
+                    p.media().unwrap().get_node(0).id(),
+                    &Id::parse("gid://shopify/MediaImage/9").unwrap()
+                );
+
+                let variant = p.variants().unwrap().get_node(0);
+                assert_eq!(
+                    variant.metafield("custom", "color").unwrap().value(),
+                    "#ff0000"
+                );
+                assert!(p.metafield("custom", "color").is_none());
+            }
+            r => panic!("unexpected record: {:?}", r),
+        }
+    }
+
+    #[tokio::test]
+    async fn reattaches_children_to_their_parents() {
+        let jsonl = concat!(
+            r#"{"id":"gid://shopify/Product/1","title":"Shirt"}"#,
+            "\n",
+            r#"{"id":"gid://shopify/ProductVariant/11","sku":"red","__parentId":"gid://shopify/Product/1"}"#,
+            "\n",
+            r#"{"id":"gid://shopify/ProductVariant/12","sku":"blue","__parentId":"gid://shopify/Product/1"}"#,
+            "\n",
+            r#"{"id":"gid://shopify/Product/2","title":"Hat"}"#,
+            "\n",
+        );
+
+        let records: Vec<_> = BulkResultReader::new(jsonl.as_bytes())
+            .into_stream()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        match &records[0] {
+            BulkRecord::Product(p) => {
+                let skus: Vec<_> = p.variants().unwrap().iter().map(|v| v.sku()).collect();
+                assert_eq!(skus, vec![Some(&"red".into()), Some(&"blue".into())]);
+            }
+            r => panic!("unexpected record: {:?}", r),
+        }
+        match &records[1] {
+            BulkRecord::Product(p) => assert!(p.variants().is_none()),
+            r => panic!("unexpected record: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn writes_one_line_per_mutation() {
+        let updates = ["1", "2"].map(|id| {
+            ProductVariantQueryBuilder::product_variant_update(Id::product_variant(id).unwrap())
+                .update_sku("a \"quoted\" sku")
+        });
+
+        match BulkOperationBuilder::mutation(updates).kind {
+            BulkOperationKind::Mutation {
+                mutation,
+                variables,
+            } => {
+                assert_eq!(
+                    mutation,
+                    "mutation productVariantUpdate($input: ProductVariantInput!) { productVariantUpdate(input: $input) { productVariant { id } userErrors { field message } } }"
+                );
+
+                let lines: Vec<Value> = variables
+                    .lines()
+                    .map(|l| serde_json::from_str(l).unwrap())
+                    .collect();
+                assert_eq!(
+                    lines,
+                    vec![
+                        serde_json::json!({ "input": { "id": "gid://shopify/ProductVariant/1", "inventoryItem": { "sku": "a \"quoted\" sku" } } }),
+                        serde_json::json!({ "input": { "id": "gid://shopify/ProductVariant/2", "inventoryItem": { "sku": "a \"quoted\" sku" } } }),
+                    ]
+                );
+            }
+            kind => panic!("unexpected operation: {:?}", kind),
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_bulk_mutation_results() {
+        let jsonl = concat!(
+            r#"{"data":{"productVariantUpdate":{"productVariant":null,"userErrors":[{"field":["price"],"message":"Price must be positive"}]}},"__lineNumber":1}"#,
+            "\n",
+            r#"{"data":{"productVariantUpdate":{"productVariant":{"id":"gid://shopify/ProductVariant/1","sku":"red"},"userErrors":[]}},"__lineNumber":0}"#,
+            "\n",
+            r#"{"errors":[{"message":"Internal error"}],"__lineNumber":2}"#,
+            "\n",
+        );
+
+        let report = BulkMutationReport::from_reader(jsonl.as_bytes())
+            .await
+            .unwrap();
+
+        let lines: Vec<_> = report.results().iter().map(|r| r.line_number()).collect();
+        assert_eq!(lines, vec![0, 1, 2]);
+        assert!(!report.is_success());
+
+        let first = &report.results()[0];
+        assert!(first.is_success());
+        assert_eq!(
+            first.record().unwrap().id(),
+            Id::product_variant("1").unwrap().into_any()
+        );
+
+        let failures: Vec<_> = report.failures().collect();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(
+            failures[0].user_errors()[0].message(),
+            "Price must be positive"
+        );
+        assert!(failures[0].record().is_none());
+        assert_eq!(failures[1].errors()[0].message(), "Internal error");
+    }
+
+    #[test]
+    fn parses_records() {
+        let (record, parent) = BulkRecord::from_line(
+            r#"{"id":"gid://shopify/ProductVariant/2","sku":"12345-red","__parentId":"gid://shopify/Product/1"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(parent.unwrap(), Id::product("1").unwrap().into_any());
+        match record {
+            BulkRecord::ProductVariant(v) => assert_eq!(v.sku().unwrap(), "12345-red"),
+            r => panic!("unexpected record: {:?}", r),
+        }
     }
 }
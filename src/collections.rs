@@ -0,0 +1,771 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    common::{resource, stream_connection, Edges, Id, Seo},
+    products::{
+        connection_query, quote, ProductsConnection, CONNECTION_ARGUMENTS, CONNECTION_VARIABLES,
+    },
+    utils::{
+        mutation_payload, run_request, GraphQlRequest, ResponseTypes, ShopifyClient,
+        ShopifyGqlError, ShopifyResult,
+    },
+};
+
+/// A group of products, either picked by hand (a custom collection) or
+/// matched by a [`CollectionRuleSet`] (a smart collection).
+///
+/// Only the fields selected on the [`CollectionQueryBuilder`] are populated;
+/// the rest are `None`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Collection {
+    id: Id<resource::Collection>,
+    title: Option<String>,
+    handle: Option<String>,
+    description_html: Option<String>,
+    sort_order: Option<CollectionSortOrder>,
+
+    /// `None` for custom collections, even when the field was selected.
+    rule_set: Option<CollectionRuleSet>,
+
+    seo: Option<Seo>,
+    template_suffix: Option<String>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl Collection {
+    pub fn id(&self) -> &Id<resource::Collection> {
+        &self.id
+    }
+
+    pub fn title(&self) -> Option<&String> {
+        self.title.as_ref()
+    }
+
+    pub fn handle(&self) -> Option<&String> {
+        self.handle.as_ref()
+    }
+
+    pub fn description_html(&self) -> Option<&String> {
+        self.description_html.as_ref()
+    }
+
+    /// The order in which the collection's products are listed.
+    pub fn sort_order(&self) -> Option<&CollectionSortOrder> {
+        self.sort_order.as_ref()
+    }
+
+    /// The rules that pick the products of a smart collection.
+    pub fn rule_set(&self) -> Option<&CollectionRuleSet> {
+        self.rule_set.as_ref()
+    }
+
+    pub fn seo(&self) -> Option<&Seo> {
+        self.seo.as_ref()
+    }
+
+    /// The suffix of the theme template used to render the collection, such
+    /// as `special` for `collection.special.liquid`.
+    pub fn template_suffix(&self) -> Option<&String> {
+        self.template_suffix.as_ref()
+    }
+
+    pub fn updated_at(&self) -> Option<&DateTime<Utc>> {
+        self.updated_at.as_ref()
+    }
+}
+
+/// The order in which a collection's products are listed.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum CollectionSortOrder {
+    ALPHA_ASC,
+    ALPHA_DESC,
+    BEST_SELLING,
+    CREATED,
+    CREATED_DESC,
+
+    /// The order the products were arranged in by hand.
+    MANUAL,
+
+    PRICE_ASC,
+    PRICE_DESC,
+}
+
+/// The rules of a smart collection.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionRuleSet {
+    /// Whether a product has to match any of the rules rather than all of
+    /// them.
+    applied_disjunctively: bool,
+
+    rules: Vec<CollectionRule>,
+}
+
+impl CollectionRuleSet {
+    /// Rules that a product has to match all of, or any of when
+    /// `applied_disjunctively` is set.
+    pub fn new(applied_disjunctively: bool, rules: Vec<CollectionRule>) -> Self {
+        Self {
+            applied_disjunctively,
+            rules,
+        }
+    }
+
+    pub fn applied_disjunctively(&self) -> bool {
+        self.applied_disjunctively
+    }
+
+    pub fn rules(&self) -> &[CollectionRule] {
+        &self.rules
+    }
+}
+
+/// A condition on a product attribute, such as `TAG EQUALS sale`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct CollectionRule {
+    column: CollectionRuleColumn,
+    relation: CollectionRuleRelation,
+    condition: String,
+}
+
+impl CollectionRule {
+    pub fn new(
+        column: CollectionRuleColumn,
+        relation: CollectionRuleRelation,
+        condition: &str,
+    ) -> Self {
+        Self {
+            column,
+            relation,
+            condition: condition.into(),
+        }
+    }
+
+    pub fn column(&self) -> &CollectionRuleColumn {
+        &self.column
+    }
+
+    pub fn relation(&self) -> &CollectionRuleRelation {
+        &self.relation
+    }
+
+    pub fn condition(&self) -> &str {
+        &self.condition
+    }
+}
+
+/// The product attribute that a [`CollectionRule`] checks.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum CollectionRuleColumn {
+    /// Whether the variant's price is below its compare-at price.
+    IS_PRICE_REDUCED,
+
+    PRODUCT_CATEGORY_ID,
+    PRODUCT_METAFIELD_DEFINITION,
+    PRODUCT_TAXONOMY_NODE_ID,
+    TAG,
+    TITLE,
+    TYPE,
+    VARIANT_COMPARE_AT_PRICE,
+    VARIANT_INVENTORY,
+    VARIANT_METAFIELD_DEFINITION,
+    VARIANT_PRICE,
+    VARIANT_TITLE,
+    VARIANT_WEIGHT,
+    VENDOR,
+}
+
+/// How a [`CollectionRule`] compares the attribute with its condition.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum CollectionRuleRelation {
+    CONTAINS,
+    ENDS_WITH,
+    EQUALS,
+    GREATER_THAN,
+    IS_NOT_SET,
+    IS_SET,
+    LESS_THAN,
+    NOT_CONTAINS,
+    NOT_EQUALS,
+    STARTS_WITH,
+}
+
+/// The keys that a `collections` query can be sorted by.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum CollectionSortKeys {
+    ID,
+
+    /// Sort by relevance to the search terms, when a `query` is given.
+    RELEVANCE,
+
+    TITLE,
+    UPDATED_AT,
+}
+
+/// All possible queries and mutations on a `Collection`.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum CollectionQueryType {
+    Collection,
+    CollectionByHandle(String),
+    Collections(ProductsConnection),
+    CollectionCreate,
+    CollectionUpdate(Id<resource::Collection>),
+    CollectionDelete(Id<resource::Collection>),
+    CollectionAddProducts(Id<resource::Collection>),
+    CollectionRemoveProducts(Id<resource::Collection>),
+}
+
+/// Builds and runs a query or mutation that returns a [`Collection`].
+// NOTE: This needs to be updated anytime a new field is added to `Collection`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionQueryBuilder {
+    id: Id<resource::Collection>,
+    fields: HashMap<String, PhantomData<u8>>,
+    inputs: Option<Map<String, Value>>,
+    query_type: CollectionQueryType,
+
+    /// The search `query:` of a `collections` query.
+    search: Option<String>,
+
+    sort_key: Option<CollectionSortKeys>,
+    reverse: bool,
+}
+
+impl CollectionQueryBuilder {
+    fn new(id: Id<resource::Collection>, query_type: CollectionQueryType) -> Self {
+        let mut fields = HashMap::new();
+        fields.insert("id".into(), PhantomData);
+
+        CollectionQueryBuilder {
+            id,
+            fields,
+            inputs: None,
+            query_type,
+            search: None,
+            sort_key: None,
+            reverse: false,
+        }
+    }
+
+    /// Fetches the collection with the given ID.
+    pub fn collection(id: Id<resource::Collection>) -> Self {
+        Self::new(id, CollectionQueryType::Collection)
+    }
+
+    /// Fetches the collection with the given handle, such as `summer-sale`.
+    pub fn collection_by_handle(handle: &str) -> Self {
+        Self::new(
+            Id::default(),
+            CollectionQueryType::CollectionByHandle(handle.into()),
+        )
+    }
+
+    /// Fetches a page of the store's collections.
+    ///
+    /// Narrow the results down with [`search`](Self::search), order them with
+    /// [`sort_key`](Self::sort_key) and [`reverse`](Self::reverse), then run
+    /// the query with [`build_connection`](Self::build_connection) or
+    /// [`stream`](Self::stream).
+    ///
+    /// Pass it to [`ProductQueryBuilder::collections`](crate::products::product::ProductQueryBuilder::collections)
+    /// to select the collections that a product belongs to instead.
+    pub fn collections(conn: ProductsConnection) -> Self {
+        Self::new(Id::default(), CollectionQueryType::Collections(conn))
+    }
+
+    /// Creates a collection and returns it.
+    ///
+    /// Set the new collection's fields with the `update_` methods, and its
+    /// initial products with [`with_products`](Self::with_products). Setting a
+    /// rule set with [`update_rule_set`](Self::update_rule_set) makes it a
+    /// smart collection.
+    pub fn collection_create() -> Self {
+        CollectionQueryBuilder {
+            inputs: Some(Map::new()),
+            ..Self::new(Id::default(), CollectionQueryType::CollectionCreate)
+        }
+    }
+
+    /// Updates the collection with the given ID.
+    ///
+    ///**NOTE:** Only call the `update_` methods on the returned builder.
+    pub fn collection_update(id: Id<resource::Collection>) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("id".into(), id.inner().clone().into());
+
+        let query_type = CollectionQueryType::CollectionUpdate(id.clone());
+
+        CollectionQueryBuilder {
+            inputs: Some(inputs),
+            ..Self::new(id, query_type)
+        }
+    }
+
+    /// Deletes the collection with the given ID.
+    ///
+    /// Only the `id` of the returned collection is set.
+    pub fn collection_delete(id: Id<resource::Collection>) -> Self {
+        Self::new(id.clone(), CollectionQueryType::CollectionDelete(id))
+    }
+
+    /// Adds products to the custom collection with the given ID, and returns
+    /// the collection.
+    pub fn collection_add_products(
+        id: Id<resource::Collection>,
+        product_ids: &[Id<resource::Product>],
+    ) -> Self {
+        Self::membership_mutation(CollectionQueryType::CollectionAddProducts(id), product_ids)
+    }
+
+    /// Removes products from the custom collection with the given ID.
+    ///
+    /// Shopify removes the products in the background, so only the `id` of
+    /// the returned collection is set.
+    pub fn collection_remove_products(
+        id: Id<resource::Collection>,
+        product_ids: &[Id<resource::Product>],
+    ) -> Self {
+        Self::membership_mutation(
+            CollectionQueryType::CollectionRemoveProducts(id),
+            product_ids,
+        )
+    }
+
+    /// Shared by [`collection_add_products`](Self::collection_add_products) and
+    /// [`collection_remove_products`](Self::collection_remove_products), which
+    /// both take the collection's `id` and a list of `productIds`.
+    fn membership_mutation(
+        query_type: CollectionQueryType,
+        product_ids: &[Id<resource::Product>],
+    ) -> Self {
+        let id = match &query_type {
+            CollectionQueryType::CollectionAddProducts(id)
+            | CollectionQueryType::CollectionRemoveProducts(id) => id.clone(),
+
+            _ => unreachable!(),
+        };
+
+        let mut inputs = Map::new();
+        inputs.insert("id".into(), id.inner().clone().into());
+        inputs.insert("productIds".into(), json!(product_ids));
+
+        CollectionQueryBuilder {
+            inputs: Some(inputs),
+            ..Self::new(id, query_type)
+        }
+    }
+
+    /// Sets the initial products of a custom collection.
+    pub fn with_products(mut self, product_ids: &[Id<resource::Product>]) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("products".into(), json!(product_ids));
+        self
+    }
+
+    /// Only returns collections that match `query`, in Shopify's search
+    /// syntax, such as `collection_type:smart`.
+    pub fn search(mut self, query: &str) -> Self {
+        self.search = Some(query.into());
+        self
+    }
+
+    pub fn sort_key(mut self, sort_key: CollectionSortKeys) -> Self {
+        self.sort_key = Some(sort_key);
+        self
+    }
+
+    /// Reverses the order of the results.
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    pub fn title(mut self) -> Self {
+        self.fields.insert("title".into(), PhantomData);
+        self
+    }
+
+    pub fn update_title(mut self, title: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("title".into(), title.into());
+        self
+    }
+
+    pub fn handle(mut self) -> Self {
+        self.fields.insert("handle".into(), PhantomData);
+        self
+    }
+
+    pub fn update_handle(mut self, handle: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("handle".into(), handle.into());
+        self
+    }
+
+    pub fn description_html(mut self) -> Self {
+        self.fields.insert("descriptionHtml".into(), PhantomData);
+        self
+    }
+
+    pub fn update_description_html(mut self, description_html: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("descriptionHtml".into(), description_html.into());
+        self
+    }
+
+    pub fn sort_order(mut self) -> Self {
+        self.fields.insert("sortOrder".into(), PhantomData);
+        self
+    }
+
+    pub fn update_sort_order(mut self, sort_order: CollectionSortOrder) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("sortOrder".into(), json!(sort_order));
+        self
+    }
+
+    pub fn rule_set(mut self) -> Self {
+        self.fields.insert(
+            "ruleSet { appliedDisjunctively rules { column relation condition } }".into(),
+            PhantomData,
+        );
+        self
+    }
+
+    /// Sets the rules of a smart collection.
+    ///
+    /// A custom collection can't be turned into a smart one, or the other way
+    /// around, once it has been created.
+    pub fn update_rule_set(mut self, rule_set: CollectionRuleSet) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("ruleSet".into(), json!(rule_set));
+        self
+    }
+
+    pub fn seo(mut self) -> Self {
+        self.fields
+            .insert("seo { title description }".into(), PhantomData);
+        self
+    }
+
+    pub fn update_seo(mut self, seo: Seo) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("seo".into(), json!(seo));
+        self
+    }
+
+    pub fn template_suffix(mut self) -> Self {
+        self.fields.insert("templateSuffix".into(), PhantomData);
+        self
+    }
+
+    pub fn update_template_suffix(mut self, template_suffix: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("templateSuffix".into(), template_suffix.into());
+        self
+    }
+
+    pub fn updated_at(mut self) -> Self {
+        self.fields.insert("updatedAt".into(), PhantomData);
+        self
+    }
+
+    pub(crate) fn query_type(&self) -> &CollectionQueryType {
+        &self.query_type
+    }
+
+    /// Renders the selected fields.
+    pub(crate) fn selection(&self) -> String {
+        let fields: Vec<&str> = self.fields.keys().map(|v| v.as_str()).collect();
+        fields.join("\n,")
+    }
+
+    /// The values of the `$query`, `$sortKey` and `$reverse` variables of a
+    /// `collections` query.
+    fn filter_variables(&self) -> Map<String, Value> {
+        let mut vars = Map::new();
+
+        if let Some(search) = &self.search {
+            vars.insert("query".into(), search.clone().into());
+        }
+        if let Some(sort_key) = &self.sort_key {
+            vars.insert("sortKey".into(), json!(sort_key));
+        }
+        if self.reverse {
+            vars.insert("reverse".into(), true.into());
+        }
+
+        vars
+    }
+
+    /// The connection arguments of a nested `collections` field, such as a
+    /// product's, with the filters inlined since the field can't share the
+    /// top-level variables.
+    ///
+    /// Without `conn`, the pagination arguments are left out, as bulk
+    /// operations require.
+    pub(crate) fn nested_arguments(&self, conn: Option<&ProductsConnection>) -> String {
+        let mut args: Vec<String> = conn
+            .map(ProductsConnection::arguments)
+            .into_iter()
+            .collect();
+
+        if let Some(search) = &self.search {
+            args.push(format!("query: {}", quote(search)));
+        }
+        if let Some(sort_key) = &self.sort_key {
+            args.push(format!("sortKey: {:?}", sort_key));
+        }
+        if self.reverse {
+            args.push("reverse: true".into());
+        }
+
+        args.join(", ")
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let fields = self.selection();
+
+        match &self.query_type {
+            CollectionQueryType::Collection => GraphQlRequest::new(format!(
+                "query collection($id: ID!) {{ collection(id: $id) {{ {} }} }}",
+                fields
+            ))
+            .with_variable("id", self.id.inner().clone()),
+
+            CollectionQueryType::CollectionByHandle(handle) => GraphQlRequest::new(format!(
+                "query collectionByHandle($handle: String!) {{ collectionByHandle(handle: $handle) {{ {} }} }}",
+                fields
+            ))
+            .with_variable("handle", handle.clone()),
+
+            CollectionQueryType::Collections(conn) => GraphQlRequest::new(format!(
+                "query collections({}, $query: String, $sortKey: CollectionSortKeys, $reverse: Boolean) {{ {} }}",
+                CONNECTION_VARIABLES,
+                connection_query(
+                    "collections",
+                    &format!(
+                        "{}, query: $query, sortKey: $sortKey, reverse: $reverse",
+                        CONNECTION_ARGUMENTS
+                    ),
+                    &fields
+                )
+            ))
+            .with_variables(conn.variables())
+            .with_variables(self.filter_variables()),
+
+            CollectionQueryType::CollectionCreate => GraphQlRequest::new(format!(
+                "mutation collectionCreate($input: CollectionInput!) {{ collectionCreate(input: $input) {{ collection {{ {} }} userErrors {{ field message }} }} }}",
+                fields
+            ))
+            .with_variable("input", self.inputs.clone().unwrap_or_default()),
+
+            CollectionQueryType::CollectionUpdate(_) => GraphQlRequest::new(format!(
+                "mutation collectionUpdate($input: CollectionInput!) {{ collectionUpdate(input: $input) {{ collection {{ {} }} userErrors {{ field message }} }} }}",
+                fields
+            ))
+            .with_variable("input", self.inputs.clone().unwrap_or_default()),
+
+            CollectionQueryType::CollectionDelete(id) => GraphQlRequest::new(
+                "mutation collectionDelete($input: CollectionDeleteInput!) { collectionDelete(input: $input) { deletedCollectionId userErrors { field message } } }",
+            )
+            .with_variable("input", json!({ "id": id })),
+
+            CollectionQueryType::CollectionAddProducts(_) => GraphQlRequest::new(format!(
+                "mutation collectionAddProducts($id: ID!, $productIds: [ID!]!) {{ collectionAddProducts(id: $id, productIds: $productIds) {{ collection {{ {} }} userErrors {{ field message }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone().unwrap_or_default()),
+
+            CollectionQueryType::CollectionRemoveProducts(_) => GraphQlRequest::new(
+                "mutation collectionRemoveProducts($id: ID!, $productIds: [ID!]!) { collectionRemoveProducts(id: $id, productIds: $productIds) { userErrors { field message } } }",
+            )
+            .with_variables(self.inputs.clone().unwrap_or_default()),
+        }
+    }
+
+    /// Sends the query through `client` and returns the collection.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<Collection> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::Collection(c) => Ok(c),
+
+            // Unlike `collection`, this returns null for an unknown handle.
+            ResponseTypes::CollectionByHandle(c) => c.ok_or_else(|| {
+                ShopifyGqlError::ResponseError("No collection has the given handle".into())
+            }),
+
+            ResponseTypes::CollectionCreate {
+                collection,
+                user_errors,
+            }
+            | ResponseTypes::CollectionUpdate {
+                collection,
+                user_errors,
+            }
+            | ResponseTypes::CollectionAddProducts {
+                collection,
+                user_errors,
+            } => mutation_payload(collection, user_errors),
+
+            ResponseTypes::CollectionDelete {
+                deleted_collection_id,
+                user_errors,
+            } => mutation_payload(deleted_collection_id, user_errors).map(|id| Collection {
+                id,
+                ..Collection::default()
+            }),
+
+            ResponseTypes::CollectionRemoveProducts { user_errors } => {
+                mutation_payload(Some(()), user_errors)?;
+
+                Ok(Collection {
+                    id: self.id,
+                    ..Collection::default()
+                })
+            }
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Sends a [`collections`](Self::collections) query through `client` and
+    /// returns the page of collections.
+    pub async fn build_connection(
+        self,
+        client: &ShopifyClient,
+    ) -> ShopifyResult<Edges<Collection>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::Collections(c) => Ok(c),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Runs a [`collections`](Self::collections) query page by page, yielding
+    /// every matching collection until the connection is exhausted.
+    ///
+    /// Yields a single [`ShopifyGqlError::InvalidQuery`] if the builder was
+    /// not created with [`collections`](Self::collections).
+    pub fn stream(self, client: &ShopifyClient) -> impl Stream<Item = ShopifyResult<Collection>> {
+        let first = match &self.query_type {
+            CollectionQueryType::Collections(conn) => Ok(conn.clone()),
+            _ => Err(ShopifyGqlError::InvalidQuery(
+                "`stream` requires a `CollectionQueryBuilder::collections` query".into(),
+            )),
+        };
+
+        stream_connection(
+            self,
+            first,
+            client,
+            move |page, conn| page.query_type = CollectionQueryType::Collections(conn),
+            |page, client| async move { page.build_connection(&client).await },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creates_smart_collections() {
+        let request = CollectionQueryBuilder::collection_create()
+            .update_title("Sale")
+            .update_rule_set(CollectionRuleSet::new(
+                false,
+                vec![CollectionRule::new(
+                    CollectionRuleColumn::TAG,
+                    CollectionRuleRelation::EQUALS,
+                    "sale",
+                )],
+            ))
+            .title()
+            .request();
+
+        assert!(request.query().contains("collectionCreate(input: $input)"));
+        assert_eq!(
+            request.variables()["input"],
+            json!({
+                "title": "Sale",
+                "ruleSet": {
+                    "appliedDisjunctively": false,
+                    "rules": [{ "column": "TAG", "relation": "EQUALS", "condition": "sale" }]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn passes_product_ids_as_variables() {
+        let request = CollectionQueryBuilder::collection_remove_products(
+            Id::from_legacy_id("5").unwrap(),
+            &[Id::product("1").unwrap(), Id::product("2").unwrap()],
+        )
+        .request();
+
+        assert_eq!(
+            Value::Object(request.variables().clone()),
+            json!({
+                "id": "gid://shopify/Collection/5",
+                "productIds": ["gid://shopify/Product/1", "gid://shopify/Product/2"]
+            })
+        );
+    }
+
+    #[test]
+    fn parses_collection_fields() {
+        let collection: Collection = serde_json::from_value(json!({
+            "id": "gid://shopify/Collection/5",
+            "title": "Sale",
+            "sortOrder": "BEST_SELLING",
+            "ruleSet": {
+                "appliedDisjunctively": true,
+                "rules": [{ "column": "VARIANT_PRICE", "relation": "LESS_THAN", "condition": "10" }]
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            collection.sort_order(),
+            Some(&CollectionSortOrder::BEST_SELLING)
+        );
+
+        let rule_set = collection.rule_set().unwrap();
+        assert!(rule_set.applied_disjunctively());
+        assert_eq!(
+            rule_set.rules()[0].column(),
+            &CollectionRuleColumn::VARIANT_PRICE
+        );
+    }
+}
@@ -1,102 +1,980 @@
 #![allow(unused)]
 
-use crate::utils::{ShopifyGqlError, ShopifyResult};
-use serde::Deserialize;
+use crate::{
+    products::ProductsConnection,
+    utils::{ShopifyClient, ShopifyGqlError, ShopifyResult},
+};
+use futures::{stream, Future, Stream, StreamExt, TryStreamExt};
+use rust_decimal::Decimal;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    iter::Sum,
+    marker::PhantomData,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
 
-#[derive(Debug, Deserialize, PartialEq, Default, Clone)]
-#[serde(rename_all = "camelCase")]
-pub(crate) struct Id(String);
+/// The kinds of objects that have a global ID, used as the type parameter of
+/// [`Id`].
+pub mod resource {
+    use super::Resource;
+
+    macro_rules! resources {
+        ($($name:ident),* $(,)?) => {
+            $(
+                #[doc = concat!("The `", stringify!($name), "` resource.")]
+                #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+                pub enum $name {}
+
+                impl Resource for $name {
+                    const TYPE_NAME: Option<&'static str> = Some(stringify!($name));
+                }
+            )*
+        };
+    }
+
+    resources!(
+        BulkOperation,
+        Collection,
+        Customer,
+        DraftOrder,
+        ExternalVideo,
+        Fulfillment,
+        FulfillmentOrder,
+        GenericFile,
+        InventoryAdjustmentGroup,
+        InventoryItem,
+        InventoryLevel,
+        Location,
+        MediaImage,
+        Metafield,
+        MetafieldDefinition,
+        Metaobject,
+        MetaobjectDefinition,
+        Model3d,
+        Order,
+        Product,
+        ProductOption,
+        ProductOptionValue,
+        ProductVariant,
+        Publication,
+        Refund,
+        Shop,
+        Video,
+    );
+}
+
+/// A kind of object that has a global ID.
+pub trait Resource {
+    /// The type that appears in the ID, such as `Product`, or `None` if any
+    /// type is accepted.
+    const TYPE_NAME: Option<&'static str>;
+}
+
+/// An [`Id`] that may refer to any kind of object.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum AnyResource {}
+
+impl Resource for AnyResource {
+    const TYPE_NAME: Option<&'static str> = None;
+}
+
+const GID_PREFIX: &str = "gid://shopify/";
+
+/// A Shopify global ID, such as `gid://shopify/Product/123`.
+///
+/// The type parameter records what kind of object the ID refers to, so that
+/// an `Id<resource::Product>` can't be passed where a variant ID is expected.
+/// IDs are checked against it when they are parsed.
+///
+/// ```
+/// use shopify_gql::common::{resource, Id};
+///
+/// let id: Id<resource::InventoryLevel> =
+///     "gid://shopify/InventoryLevel/12?inventory_item_id=34".parse().unwrap();
+///
+/// assert_eq!(id.legacy_id(), Some(12));
+/// assert_eq!(id.query_param("inventory_item_id"), Some("34"));
+/// assert!("gid://shopify/Order/12".parse::<Id<resource::Product>>().is_err());
+/// ```
+pub struct Id<R = AnyResource> {
+    gid: String,
+    resource: PhantomData<fn() -> R>,
+}
 
 impl Id {
-    fn is_numeric(id: &str) -> bool {
-        let mut ret = true;
-        for c in id.chars() {
-            match c {
-                '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => continue,
+    /// Creates a product ID from its numeric part.
+    pub fn product(id: &str) -> ShopifyResult<Id<resource::Product>> {
+        Id::from_legacy_id(id)
+    }
+
+    /// Creates a product variant ID from its numeric part.
+    pub fn product_variant(id: &str) -> ShopifyResult<Id<resource::ProductVariant>> {
+        Id::from_legacy_id(id)
+    }
+
+    /// Creates a location ID from its numeric part.
+    pub fn location(id: &str) -> ShopifyResult<Id<resource::Location>> {
+        Id::from_legacy_id(id)
+    }
+
+    /// Creates an ID for any type of object from its type, such as `Order`,
+    /// and its numeric part.
+    pub fn from_parts(resource_type: &str, legacy_id: &str) -> ShopifyResult<Self> {
+        if resource_type.is_empty() || !resource_type.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(ShopifyGqlError::InvalidGlobalId(format!(
+                "{}{}/{}",
+                GID_PREFIX, resource_type, legacy_id
+            )));
+        }
+
+        Self::with_type(resource_type, legacy_id)
+    }
+}
+
+impl<R: Resource> Id<R> {
+    /// Creates an ID from its numeric part, also known as the legacy resource
+    /// ID.
+    ///
+    /// Returns a [`ShopifyGqlError::InvalidGlobalId`] for an
+    /// `Id<AnyResource>`, which doesn't know its type; use
+    /// [`Id::from_parts`] instead.
+    pub fn from_legacy_id(legacy_id: &str) -> ShopifyResult<Self> {
+        match R::TYPE_NAME {
+            Some(resource_type) => Self::with_type(resource_type, legacy_id),
+            None => Err(ShopifyGqlError::InvalidGlobalId(legacy_id.into())),
+        }
+    }
+
+    fn with_type(resource_type: &str, legacy_id: &str) -> ShopifyResult<Self> {
+        if legacy_id.is_empty() || !legacy_id.chars().all(|c| c.is_ascii_digit()) {
+            return Err(ShopifyGqlError::InvalidId(legacy_id.into()));
+        }
+
+        Ok(Self::new_unchecked(format!(
+            "{}{}/{}",
+            GID_PREFIX, resource_type, legacy_id
+        )))
+    }
+
+    /// Parses a `gid://shopify/...` string, checking that it refers to `R`.
+    pub fn parse(gid: &str) -> ShopifyResult<Self> {
+        let invalid = || ShopifyGqlError::InvalidGlobalId(gid.into());
+
+        let rest = gid.strip_prefix(GID_PREFIX).ok_or_else(invalid)?;
+        let (resource_type, id) = rest.split_once('/').ok_or_else(invalid)?;
+        let id = id.split('?').next().unwrap_or_default();
+
+        if resource_type.is_empty() || id.is_empty() {
+            return Err(invalid());
+        }
+        if R::TYPE_NAME.is_some_and(|expected| expected != resource_type) {
+            return Err(invalid());
+        }
+
+        Ok(Self::new_unchecked(gid.into()))
+    }
+
+    /// Converts the ID to one of another resource type, checking that it
+    /// refers to that type.
+    pub fn cast<S: Resource>(self) -> ShopifyResult<Id<S>> {
+        Id::parse(&self.gid)
+    }
+}
+
+impl<R> Id<R> {
+    fn new_unchecked(gid: String) -> Self {
+        Self {
+            gid,
+            resource: PhantomData,
+        }
+    }
+
+    /// Returns the full `gid://` string.
+    pub fn inner(&self) -> &String {
+        &self.gid
+    }
+
+    /// The type of object the ID refers to, such as `Product`.
+    pub fn resource_type(&self) -> &str {
+        self.path().split('/').next().unwrap_or_default()
+    }
+
+    /// The numeric part of the ID, which the REST API and the admin use.
+    ///
+    /// `None` if the ID isn't numeric.
+    pub fn legacy_id(&self) -> Option<u64> {
+        self.path().split_once('/')?.1.parse().ok()
+    }
+
+    /// Returns the value of a query parameter of the ID, such as the
+    /// `inventory_item_id` of `gid://shopify/InventoryLevel/1?inventory_item_id=2`.
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        let (_, query) = self.gid.split_once('?')?;
+
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Forgets the resource type of the ID.
+    pub fn into_any(self) -> Id<AnyResource> {
+        Id::new_unchecked(self.gid)
+    }
+
+    /// The ID without the `gid://shopify/` prefix or query string.
+    fn path(&self) -> &str {
+        let path = self.gid.strip_prefix(GID_PREFIX).unwrap_or_default();
+        path.split('?').next().unwrap_or_default()
+    }
+}
+
+impl<R: Resource> FromStr for Id<R> {
+    type Err = ShopifyGqlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Id::parse(s)
+    }
+}
+
+impl<R> fmt::Display for Id<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.gid)
+    }
+}
+
+impl<R> fmt::Debug for Id<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Id").field(&self.gid).finish()
+    }
+}
+
+impl<R> Clone for Id<R> {
+    fn clone(&self) -> Self {
+        Id::new_unchecked(self.gid.clone())
+    }
+}
+
+impl<R> Default for Id<R> {
+    fn default() -> Self {
+        Id::new_unchecked(String::new())
+    }
+}
+
+/// IDs of the same resource type are equal if they refer to the same object.
+/// Compare IDs of different types through [`Id::into_any`].
+impl<R> PartialEq for Id<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.gid == other.gid
+    }
+}
+
+impl<R> Eq for Id<R> {}
+
+impl<R> Hash for Id<R> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.gid.hash(state);
+    }
+}
+
+impl<R> From<Id<R>> for String {
+    fn from(id: Id<R>) -> Self {
+        id.gid
+    }
+}
+
+impl<R> Serialize for Id<R> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.gid)
+    }
+}
+
+impl<'de, R: Resource> Deserialize<'de> for Id<R> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let gid = String::deserialize(deserializer)?;
+        Id::parse(&gid).map_err(de::Error::custom)
+    }
+}
+
+/// The ISO 4217 code of a currency.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum CurrencyCode {
+    AED,
+    AFN,
+    ALL,
+    AMD,
+    ANG,
+    AOA,
+    ARS,
+    AUD,
+    AWG,
+    AZN,
+    BAM,
+    BBD,
+    BDT,
+    BGN,
+    BHD,
+    BIF,
+    BMD,
+    BND,
+    BOB,
+    BRL,
+    BSD,
+    BTN,
+    BWP,
+    BYN,
+    BYR,
+    BZD,
+    CAD,
+    CDF,
+    CHF,
+    CLP,
+    CNY,
+    COP,
+    CRC,
+    CVE,
+    CZK,
+    DJF,
+    DKK,
+    DOP,
+    DZD,
+    EGP,
+    ERN,
+    ETB,
+    EUR,
+    FJD,
+    FKP,
+    GBP,
+    GEL,
+    GHS,
+    GIP,
+    GMD,
+    GNF,
+    GTQ,
+    GYD,
+    HKD,
+    HNL,
+    HRK,
+    HTG,
+    HUF,
+    IDR,
+    ILS,
+    INR,
+    IQD,
+    IRR,
+    ISK,
+    JEP,
+    JMD,
+    JOD,
+    JPY,
+    KES,
+    KGS,
+    KHR,
+    KID,
+    KMF,
+    KRW,
+    KWD,
+    KYD,
+    KZT,
+    LAK,
+    LBP,
+    LKR,
+    LRD,
+    LSL,
+    LTL,
+    LVL,
+    LYD,
+    MAD,
+    MDL,
+    MGA,
+    MKD,
+    MMK,
+    MNT,
+    MOP,
+    MRU,
+    MUR,
+    MVR,
+    MWK,
+    MXN,
+    MYR,
+    MZN,
+    NAD,
+    NGN,
+    NIO,
+    NOK,
+    NPR,
+    NZD,
+    OMR,
+    PAB,
+    PEN,
+    PGK,
+    PHP,
+    PKR,
+    PLN,
+    PYG,
+    QAR,
+    RON,
+    RSD,
+    RUB,
+    RWF,
+    SAR,
+    SBD,
+    SCR,
+    SDG,
+    SEK,
+    SGD,
+    SHP,
+    SLL,
+    SOS,
+    SRD,
+    SSP,
+    STD,
+    STN,
+    SYP,
+    SZL,
+    THB,
+    TJS,
+    TMT,
+    TND,
+    TOP,
+    TRY,
+    TTD,
+    TWD,
+    TZS,
+    UAH,
+    UGX,
+    USD,
+    USDC,
+    UYU,
+    UZS,
+    VED,
+    VEF,
+    VES,
+    VND,
+    VUV,
+    WST,
+    XAF,
+    XCD,
+    XOF,
+    XPF,
+    YER,
+    ZAR,
+    ZMW,
+
+    /// Shopify's code for an unrecognized currency.
+    XXX,
+
+    /// A currency that Shopify added after this enum was last updated, kept
+    /// so that it is sent back unchanged.
+    #[serde(untagged)]
+    Other(UnknownCurrencyCode),
+}
+
+/// Formats the three-letter code, such as `USD`.
+impl fmt::Display for CurrencyCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurrencyCode::Other(code) => f.write_str(code.as_str()),
+            known => write!(f, "{:?}", known),
+        }
+    }
+}
+
+/// A three-letter currency code that [`CurrencyCode`] doesn't list.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct UnknownCurrencyCode([u8; 3]);
+
+impl UnknownCurrencyCode {
+    pub fn as_str(&self) -> &str {
+        // Only built from ASCII letters.
+        std::str::from_utf8(&self.0).unwrap_or_default()
+    }
+}
+
+impl Serialize for UnknownCurrencyCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for UnknownCurrencyCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+
+        match <[u8; 3]>::try_from(code.as_bytes()) {
+            Ok(bytes) if bytes.iter().all(u8::is_ascii_uppercase) => Ok(Self(bytes)),
+            _ => Err(de::Error::custom(format!(
+                "{} is not a three-letter currency code",
+                code
+            ))),
+        }
+    }
+}
+
+/// A monetary amount, held as an exact decimal.
+///
+/// Shopify sends amounts either as a plain decimal string, such as a variant's
+/// `price`, or as a `MoneyV2` object with a currency; both deserialize into
+/// `Money`, and the currency is `None` for the former.
+///
+/// ```
+/// use shopify_gql::common::{CurrencyCode, Money};
+///
+/// let price: Money = "19.99".parse().unwrap();
+/// let total = price * 3.into() - "0.97".parse().unwrap();
+///
+/// assert_eq!(total.to_string(), "59.00");
+/// assert_eq!(
+///     total.with_currency(CurrencyCode::EUR).to_string(),
+///     "59.00 EUR"
+/// );
+/// ```
+///
+/// `Money` serializes back into the shape it came from: a plain string
+/// without a currency, or a `MoneyV2` object with one.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[serde(from = "MoneyRepr", into = "MoneyRepr")]
+pub struct Money {
+    amount: Decimal,
+    currency_code: Option<CurrencyCode>,
+}
+
+/// The shapes that Shopify sends amounts in.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum MoneyRepr {
+    Scalar(Decimal),
+    V2(MoneyV2),
+}
+
+impl From<MoneyRepr> for Money {
+    fn from(repr: MoneyRepr) -> Self {
+        match repr {
+            MoneyRepr::Scalar(amount) => Money::new(amount),
+            MoneyRepr::V2(money) => money.into(),
+        }
+    }
+}
+
+impl From<Money> for MoneyRepr {
+    fn from(money: Money) -> Self {
+        match money.currency_code {
+            Some(currency_code) => MoneyRepr::V2(MoneyV2::new(money.amount, currency_code)),
+            None => MoneyRepr::Scalar(money.amount),
+        }
+    }
+}
+
+impl Money {
+    /// An amount without a currency.
+    pub fn new(amount: Decimal) -> Self {
+        Self {
+            amount,
+            currency_code: None,
+        }
+    }
+
+    /// Sets the currency of the amount.
+    pub fn with_currency(mut self, currency_code: CurrencyCode) -> Self {
+        self.currency_code = Some(currency_code);
+        self
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    /// `None` if the amount came from a plain decimal scalar, which is in the
+    /// store's currency.
+    pub fn currency_code(&self) -> Option<CurrencyCode> {
+        self.currency_code
+    }
+
+    /// Returns the sum of both amounts, or `None` if they are in different
+    /// currencies.
+    ///
+    /// An amount without a currency takes on the currency of the other.
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        let currency_code = self.common_currency(&other)?;
 
-                _ => return false,
-            }
+        Some(Money {
+            amount: self.amount.checked_add(other.amount)?,
+            currency_code,
+        })
+    }
+
+    /// Returns the difference of both amounts, or `None` if they are in
+    /// different currencies.
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.checked_add(-other)
+    }
+
+    /// Rounds the amount to `dp` decimal places, rounding half to even as
+    /// Shopify does.
+    pub fn round_dp(self, dp: u32) -> Money {
+        Money {
+            amount: self.amount.round_dp(dp),
+            ..self
         }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
 
-        ret
+    pub fn is_negative(&self) -> bool {
+        self.amount.is_sign_negative() && !self.amount.is_zero()
     }
 
-    pub(crate) fn product(id: &str) -> ShopifyResult<Self> {
-        // Validate input (must be numbers only)
-        if !Id::is_numeric(id) {
-            return Err(ShopifyGqlError::InvalidId(id.into()));
+    fn common_currency(&self, other: &Money) -> Option<Option<CurrencyCode>> {
+        match (self.currency_code, other.currency_code) {
+            (Some(a), Some(b)) if a != b => None,
+            (a, b) => Some(a.or(b)),
         }
+    }
+}
+
+impl From<Decimal> for Money {
+    fn from(amount: Decimal) -> Self {
+        Money::new(amount)
+    }
+}
+
+impl FromStr for Money {
+    type Err = ShopifyGqlError;
 
-        Ok(Self(format!("gid://shopify/Product/{}", id)))
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let amount = Decimal::from_str_exact(s.trim())
+            .map_err(|_| ShopifyGqlError::InvalidMoney(s.into()))?;
+
+        Ok(Money::new(amount))
     }
+}
 
-    pub(crate) fn product_variant(id: &str) -> ShopifyResult<Self> {
-        // Validate input (must be numbers only)
-        if !Id::is_numeric(id) {
-            return Err(ShopifyGqlError::InvalidId(id.into()));
+/// Formats the amount exactly as it was given, followed by the currency code
+/// if there is one.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.amount)?;
+        if let Some(currency_code) = self.currency_code {
+            write!(f, " {}", currency_code)?;
         }
 
-        Ok(Self(format!("gid://shopify/ProductVariant/{}", id)))
+        Ok(())
     }
+}
+
+/// # Panics
+///
+/// Panics if the amounts are in different currencies. Use
+/// [`Money::checked_add`] to handle that case.
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        let (a, b) = (self.currency_code, other.currency_code);
+        self.checked_add(other)
+            .unwrap_or_else(|| panic!("cannot add amounts in {:?} and {:?}", a, b))
+    }
+}
+
+/// # Panics
+///
+/// Panics if the amounts are in different currencies. Use
+/// [`Money::checked_sub`] to handle that case.
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        self + -other
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
 
-    pub(crate) fn location(id: &str) -> ShopifyResult<Self> {
-        // Validate input (must be numbers only)
-        if !Id::is_numeric(id) {
-            return Err(ShopifyGqlError::InvalidId(id.into()));
+    fn neg(self) -> Money {
+        Money {
+            amount: -self.amount,
+            ..self
         }
+    }
+}
 
-        Ok(Self(format!("gid://shopify/Location/{}", id)))
+impl Mul<Decimal> for Money {
+    type Output = Money;
+
+    fn mul(self, factor: Decimal) -> Money {
+        Money {
+            amount: self.amount * factor,
+            ..self
+        }
     }
+}
 
-    pub(crate) fn inner(&self) -> &String {
-        &self.0
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::default(), Add::add)
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
-#[serde(rename_all = "camelCase", try_from = "String")]
-pub(crate) struct Money(pub f64);
+/// An amount along with its currency, as in Shopify's `MoneyV2` type.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct MoneyV2 {
+    amount: Decimal,
+    currency_code: CurrencyCode,
+}
+
+impl MoneyV2 {
+    pub fn new(amount: Decimal, currency_code: CurrencyCode) -> Self {
+        Self {
+            amount,
+            currency_code,
+        }
+    }
 
-impl TryFrom<String> for Money {
-    type Error = ShopifyGqlError;
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
 
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        let v: f64 = value
-            .parse()
-            .map_err(|_| ShopifyGqlError::FloatParseError(value))?;
+    pub fn currency_code(&self) -> CurrencyCode {
+        self.currency_code
+    }
+}
 
-        Ok(Money(v))
+impl From<MoneyV2> for Money {
+    fn from(money: MoneyV2) -> Self {
+        Money::new(money.amount).with_currency(money.currency_code)
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
-pub(crate) enum WeightUnit {
+/// Units of measurement for weight.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum WeightUnit {
     GRAMS,
     KILOGRAMS,
     OUNCES,
     POUNDS,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+/// The title and description that search engines show for a page.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Seo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+impl Seo {
+    /// Fields left as `None` are left unchanged when used as an input.
+    pub fn new(title: Option<&str>, description: Option<&str>) -> Self {
+        Self {
+            title: title.map(String::from),
+            description: description.map(String::from),
+        }
+    }
+
+    pub fn title(&self) -> Option<&String> {
+        self.title.as_ref()
+    }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+}
+
+/// A single edge of a connection.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct Node<T> {
+pub struct Node<T> {
+    cursor: Option<String>,
     node: T,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+impl<T> Node<T> {
+    /// The cursor to pass to [`ProductsConnection::After`] or
+    /// [`ProductsConnection::Before`] to paginate from this edge.
+    pub fn cursor(&self) -> Option<&String> {
+        self.cursor.as_ref()
+    }
+
+    pub fn node(&self) -> &T {
+        &self.node
+    }
+}
+
+/// Where a page sits within a connection.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    has_next_page: bool,
+    has_previous_page: bool,
+    start_cursor: Option<String>,
+    end_cursor: Option<String>,
+}
+
+impl PageInfo {
+    pub fn has_next_page(&self) -> bool {
+        self.has_next_page
+    }
+
+    pub fn has_previous_page(&self) -> bool {
+        self.has_previous_page
+    }
+
+    /// The cursor of the first edge in the page.
+    pub fn start_cursor(&self) -> Option<&String> {
+        self.start_cursor.as_ref()
+    }
+
+    /// The cursor of the last edge in the page.
+    pub fn end_cursor(&self) -> Option<&String> {
+        self.end_cursor.as_ref()
+    }
+}
+
+/// The edges of a paginated connection, such as a product's `variants`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct Edges<T> {
+pub struct Edges<T> {
     edges: Vec<Node<T>>,
+    page_info: Option<PageInfo>,
 }
 
 impl<T> Edges<T> {
-    pub(crate) fn to_inner_vec(&self) -> Vec<&T> {
+    /// Returns the edges of the connection, including their cursors.
+    pub fn edges(&self) -> &[Node<T>] {
+        &self.edges
+    }
+
+    pub fn page_info(&self) -> Option<&PageInfo> {
+        self.page_info.as_ref()
+    }
+
+    /// Returns references to every node in the connection.
+    pub fn to_inner_vec(&self) -> Vec<&T> {
         self.edges.iter().map(|e| &e.node).collect()
     }
 
-    pub(crate) fn get_node(&self, idx: usize) -> &T {
+    /// Returns the node at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn get_node(&self, idx: usize) -> &T {
         &self.edges[idx].node
     }
+
+    /// Returns the node at `idx`, or `None` if it is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.edges.get(idx).map(|e| &e.node)
+    }
+
+    /// Iterates over the nodes in the connection.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.edges.iter().map(|e| &e.node)
+    }
+
+    /// Consumes the connection and returns its nodes.
+    pub fn into_inner_vec(self) -> Vec<T> {
+        self.edges.into_iter().map(|e| e.node).collect()
+    }
+
+    /// Returns the number of nodes in the connection.
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns `true` if the connection has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Appends a node without a cursor, for connections that are put together
+    /// locally, such as from bulk operation results.
+    pub(crate) fn push(&mut self, node: T) {
+        self.edges.push(Node { cursor: None, node });
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.edges.iter_mut().map(|e| &mut e.node)
+    }
+}
+
+impl<T> Default for Edges<T> {
+    fn default() -> Self {
+        Self {
+            edges: vec![],
+            page_info: None,
+        }
+    }
+}
+
+/// Fetches pages of a connection one after another, starting at `first`, and
+/// yields their nodes until the connection is exhausted.
+pub(crate) fn paginate<T, F, Fut>(
+    first: ProductsConnection,
+    fetch: F,
+) -> impl Stream<Item = ShopifyResult<T>>
+where
+    F: FnMut(ProductsConnection) -> Fut,
+    Fut: Future<Output = ShopifyResult<Edges<T>>>,
+{
+    stream::try_unfold((Some(first), fetch), |(conn, mut fetch)| async move {
+        let conn = match conn {
+            Some(conn) => conn,
+            None => return ShopifyResult::Ok(None),
+        };
+
+        let page = fetch(conn.clone()).await?;
+        let next = page.page_info().and_then(|info| conn.next_page(info));
+        let nodes = stream::iter(page.into_inner_vec().into_iter().map(Ok));
+
+        Ok(Some((nodes, (next, fetch))))
+    })
+    .try_flatten()
+}
+
+/// Backs the `stream` method of a query builder: [`paginate`]s from `first`,
+/// pointing a copy of `builder` at each page with `set_page` and sending it
+/// with `fetch`.
+///
+/// If `first` is an error, such as when the builder isn't a connection query,
+/// the stream yields only that error.
+pub(crate) fn stream_connection<B, T, S, F, Fut>(
+    builder: B,
+    first: ShopifyResult<ProductsConnection>,
+    client: &ShopifyClient,
+    set_page: S,
+    fetch: F,
+) -> impl Stream<Item = ShopifyResult<T>>
+where
+    B: Clone,
+    S: Fn(&mut B, ProductsConnection),
+    F: Fn(B, ShopifyClient) -> Fut,
+    Fut: Future<Output = ShopifyResult<Edges<T>>>,
+{
+    let first = match first {
+        Ok(first) => first,
+        Err(e) => return stream::once(async { Err(e) }).right_stream(),
+    };
+    let client = client.clone();
+
+    paginate(first, move |conn| {
+        let mut page = builder.clone();
+        set_page(&mut page, conn);
+        fetch(page, client.clone())
+    })
+    .left_stream()
 }
 
 #[cfg(test)]
@@ -115,4 +993,133 @@ mod tests {
             "Invalid ID (abcd): The ID must only be numbers"
         )
     }
+
+    #[test]
+    fn parses_global_ids() {
+        let id: Id<resource::Product> = "gid://shopify/Product/1235".parse().unwrap();
+        assert_eq!(id, Id::product("1235").unwrap());
+        assert_eq!(id.resource_type(), "Product");
+        assert_eq!(id.legacy_id(), Some(1235));
+        assert_eq!(id.to_string(), "gid://shopify/Product/1235");
+        assert_eq!(id.query_param("missing"), None);
+
+        let any: Id = "gid://shopify/Order/7".parse().unwrap();
+        assert_eq!(any, Id::from_parts("Order", "7").unwrap());
+        assert!(any.clone().cast::<resource::Product>().is_err());
+        assert!(any.cast::<resource::Order>().is_ok());
+
+        for invalid in ["", "Product/1", "gid://shopify/Product", "gid://shopify//1"] {
+            assert!(
+                matches!(
+                    invalid.parse::<Id>(),
+                    Err(ShopifyGqlError::InvalidGlobalId(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_ids_through_json() {
+        let id = Id::product_variant("42").unwrap();
+        let json = serde_json::to_string(&id).unwrap();
+
+        assert_eq!(json, r#""gid://shopify/ProductVariant/42""#);
+        assert_eq!(
+            serde_json::from_str::<Id<resource::ProductVariant>>(&json).unwrap(),
+            id
+        );
+        assert!(serde_json::from_str::<Id<resource::Product>>(&json).is_err());
+    }
+
+    #[test]
+    fn parses_both_money_shapes() {
+        let scalar: Money = serde_json::from_str(r#""42.99""#).unwrap();
+        assert_eq!(scalar.amount(), Decimal::new(4299, 2));
+        assert_eq!(scalar.currency_code(), None);
+
+        let v2: Money =
+            serde_json::from_str(r#"{ "amount": "42.99", "currencyCode": "CAD" }"#).unwrap();
+        assert_eq!(scalar.with_currency(CurrencyCode::CAD), v2);
+        assert_eq!(v2.to_string(), "42.99 CAD");
+
+        for money in [scalar, v2] {
+            let json = serde_json::to_string(&money).unwrap();
+            assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), money);
+        }
+    }
+
+    #[test]
+    fn parses_unknown_currency_codes() {
+        let money: Money =
+            serde_json::from_str(r#"{ "amount": "5.00", "currencyCode": "ZWG" }"#).unwrap();
+        assert!(matches!(
+            money.currency_code(),
+            Some(CurrencyCode::Other(_))
+        ));
+        assert_eq!(money.to_string(), "5.00 ZWG");
+        assert_eq!(
+            serde_json::to_value(money).unwrap(),
+            serde_json::json!({ "amount": "5.00", "currencyCode": "ZWG" })
+        );
+
+        let money: Money =
+            serde_json::from_str(r#"{ "amount": "5.00", "currencyCode": "XXX" }"#).unwrap();
+        assert_eq!(money.currency_code(), Some(CurrencyCode::XXX));
+    }
+
+    #[test]
+    fn adds_money_exactly() {
+        let dime: Money = "0.10".parse().unwrap();
+        let total: Money = [dime; 3].into_iter().sum();
+
+        assert_eq!(total, "0.3".parse().unwrap());
+        assert_eq!(total.to_string(), "0.30");
+
+        let usd = dime.with_currency(CurrencyCode::USD);
+        assert_eq!(
+            usd.checked_add(dime),
+            Some(
+                "0.20"
+                    .parse::<Money>()
+                    .unwrap()
+                    .with_currency(CurrencyCode::USD)
+            )
+        );
+        assert_eq!(usd.checked_add(dime.with_currency(CurrencyCode::EUR)), None);
+        assert!(matches!(
+            "12,50".parse::<Money>(),
+            Err(ShopifyGqlError::InvalidMoney(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn paginates_until_exhausted() {
+        let pages = [
+            r#"{ "edges": [{ "cursor": "a", "node": 1 }, { "cursor": "b", "node": 2 }],
+                 "pageInfo": { "hasNextPage": true, "hasPreviousPage": false, "startCursor": "a", "endCursor": "b" } }"#,
+            r#"{ "edges": [{ "cursor": "c", "node": 3 }],
+                 "pageInfo": { "hasNextPage": false, "hasPreviousPage": true, "startCursor": "c", "endCursor": "c" } }"#,
+        ];
+
+        let mut requested = vec![];
+        let nodes: Vec<u32> = paginate(ProductsConnection::First(2), |conn| {
+            requested.push(conn);
+            let page: Edges<u32> = serde_json::from_str(pages[requested.len() - 1]).unwrap();
+            async move { Ok(page) }
+        })
+        .try_collect()
+        .await
+        .unwrap();
+
+        assert_eq!(nodes, vec![1, 2, 3]);
+        assert_eq!(
+            requested,
+            vec![
+                ProductsConnection::First(2),
+                ProductsConnection::After(2, "b".into())
+            ]
+        );
+    }
 }
@@ -0,0 +1,1214 @@
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    common::{resource, stream_connection, Edges, Id},
+    products::{connection_query, ProductsConnection, CONNECTION_ARGUMENTS, CONNECTION_VARIABLES},
+    utils::{
+        mutation_payload, run_request, GraphQlRequest, ResponseTypes, ShopifyClient,
+        ShopifyGqlError, ShopifyResult,
+    },
+};
+
+/// The fields selected whenever a metafield definition is fetched.
+const METAFIELD_DEFINITION_FIELDS: &str =
+    "id name namespace key description ownerType type { name } validations { name value } pinnedPosition";
+
+/// The fields selected whenever a metaobject definition is fetched.
+const METAOBJECT_DEFINITION_FIELDS: &str = "id type name description displayNameKey fieldDefinitions { key name description required type { name } validations { name value } }";
+
+/// The kinds of objects that metafields can be attached to.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum MetafieldOwnerType {
+    API_PERMISSION,
+    ARTICLE,
+    BLOG,
+    CARTTRANSFORM,
+    COLLECTION,
+    COMPANY,
+    COMPANY_LOCATION,
+    CUSTOMER,
+    DELIVERY_CUSTOMIZATION,
+    DISCOUNT,
+    DRAFTORDER,
+    FULFILLMENT_CONSTRAINT_RULE,
+    GIFT_CARD_TRANSACTION,
+    LOCATION,
+    MARKET,
+    ORDER,
+    ORDER_ROUTING_LOCATION_RULE,
+    PAGE,
+    PAYMENT_CUSTOMIZATION,
+    PRODUCT,
+    PRODUCTVARIANT,
+    SELLING_PLAN,
+    SHOP,
+    VALIDATION,
+}
+
+/// A constraint on the values of a metafield or metaobject field, such as
+/// `min` or `regex`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct DefinitionValidation {
+    name: String,
+    value: Option<String>,
+}
+
+impl DefinitionValidation {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.into(),
+            value: Some(value.into()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> Option<&String> {
+        self.value.as_ref()
+    }
+}
+
+/// The `type { name }` of a definition.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+struct DefinitionType {
+    name: String,
+}
+
+/// The type, validations and other settings shared by the metafields with a
+/// given owner type, namespace and key.
+///
+/// Every field is fetched, so they are only `None` on the result of a
+/// [`metafield_definition_delete`](MetafieldDefinitionQueryBuilder::metafield_definition_delete).
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MetafieldDefinition {
+    id: Id<resource::MetafieldDefinition>,
+    name: Option<String>,
+    namespace: Option<String>,
+    key: Option<String>,
+    description: Option<String>,
+    owner_type: Option<MetafieldOwnerType>,
+
+    #[serde(rename = "type")]
+    definition_type: Option<DefinitionType>,
+
+    validations: Option<Vec<DefinitionValidation>>,
+
+    /// `None` if the definition isn't pinned.
+    pinned_position: Option<i32>,
+}
+
+impl MetafieldDefinition {
+    pub fn id(&self) -> &Id<resource::MetafieldDefinition> {
+        &self.id
+    }
+
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn namespace(&self) -> Option<&String> {
+        self.namespace.as_ref()
+    }
+
+    pub fn key(&self) -> Option<&String> {
+        self.key.as_ref()
+    }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    pub fn owner_type(&self) -> Option<&MetafieldOwnerType> {
+        self.owner_type.as_ref()
+    }
+
+    /// The type of the metafields, such as `single_line_text_field`.
+    pub fn type_name(&self) -> Option<&str> {
+        self.definition_type.as_ref().map(|t| t.name.as_str())
+    }
+
+    pub fn validations(&self) -> Option<&[DefinitionValidation]> {
+        self.validations.as_deref()
+    }
+
+    /// The position of the definition among the pinned ones in the Shopify
+    /// admin, or `None` if it isn't pinned.
+    pub fn pinned_position(&self) -> Option<i32> {
+        self.pinned_position
+    }
+}
+
+/// Declares a metafield definition, to create it or bring an existing one up
+/// to date.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MetafieldDefinitionInput {
+    owner_type: MetafieldOwnerType,
+    namespace: String,
+    key: String,
+    name: String,
+    type_name: String,
+    description: Option<String>,
+    validations: Vec<DefinitionValidation>,
+    pin: bool,
+}
+
+impl MetafieldDefinitionInput {
+    /// A definition of the metafields of `owner_type` objects with the given
+    /// namespace and key, whose values have type `type_name`, such as
+    /// `number_integer`.
+    pub fn new(
+        owner_type: MetafieldOwnerType,
+        namespace: &str,
+        key: &str,
+        name: &str,
+        type_name: &str,
+    ) -> Self {
+        Self {
+            owner_type,
+            namespace: namespace.into(),
+            key: key.into(),
+            name: name.into(),
+            type_name: type_name.into(),
+            description: None,
+            validations: vec![],
+            pin: false,
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn validation(mut self, validation: DefinitionValidation) -> Self {
+        self.validations.push(validation);
+        self
+    }
+
+    /// Pins the definition, so that its metafields show up on the owner's page
+    /// in the Shopify admin.
+    pub fn pin(mut self) -> Self {
+        self.pin = true;
+        self
+    }
+
+    /// Returns `true` if `definition` is the declared one, or `false` if
+    /// [`metafield_definition_update`](MetafieldDefinitionQueryBuilder::metafield_definition_update)
+    /// has to bring it up to date.
+    ///
+    /// The type of a definition can't be updated, so if `definition` has
+    /// another type this returns [`ShopifyGqlError::DefinitionTypeChanged`],
+    /// and the definition has to be deleted and created again.
+    pub fn is_applied_to(&self, definition: &MetafieldDefinition) -> ShopifyResult<bool> {
+        if let Some(existing) = definition.type_name() {
+            if existing != self.type_name {
+                return Err(ShopifyGqlError::DefinitionTypeChanged {
+                    key: format!("{}.{}", self.namespace, self.key),
+                    existing: existing.into(),
+                    declared: self.type_name.clone(),
+                });
+            }
+        }
+
+        Ok(definition.name.as_ref() == Some(&self.name)
+            && definition.description.as_deref().unwrap_or_default()
+                == self.description.as_deref().unwrap_or_default()
+            && definition.validations.as_deref().unwrap_or_default() == self.validations
+            && definition.pinned_position.is_some() == self.pin)
+    }
+
+    /// The `MetafieldDefinitionUpdateInput`, which identifies the definition
+    /// by its owner type, namespace and key, and can't change its type.
+    fn update_input(&self) -> Value {
+        let mut input = self.create_input();
+        input.remove("type");
+
+        input.into()
+    }
+
+    /// The `MetafieldDefinitionInput`.
+    fn create_input(&self) -> Map<String, Value> {
+        let mut input = Map::new();
+        input.insert("ownerType".into(), json!(self.owner_type));
+        input.insert("namespace".into(), self.namespace.clone().into());
+        input.insert("key".into(), self.key.clone().into());
+        input.insert("name".into(), self.name.clone().into());
+        input.insert("type".into(), self.type_name.clone().into());
+        input.insert("validations".into(), json!(self.validations));
+        input.insert("pin".into(), self.pin.into());
+
+        if let Some(description) = &self.description {
+            input.insert("description".into(), description.clone().into());
+        }
+
+        input
+    }
+}
+
+/// All possible queries and mutations on a `MetafieldDefinition`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum MetafieldDefinitionQueryType {
+    MetafieldDefinition(Id<resource::MetafieldDefinition>),
+    MetafieldDefinitions(MetafieldOwnerType, ProductsConnection),
+    MetafieldDefinitionCreate,
+    MetafieldDefinitionUpdate,
+    MetafieldDefinitionDelete,
+    MetafieldDefinitionPin,
+    MetafieldDefinitionUnpin,
+}
+
+/// Builds and runs a query or mutation that returns a
+/// [`MetafieldDefinition`].
+///
+/// Every field of the definitions is always fetched.
+#[derive(Debug, Clone)]
+pub struct MetafieldDefinitionQueryBuilder {
+    query_type: MetafieldDefinitionQueryType,
+
+    /// The variables of a mutation, or the filters of a
+    /// `metafieldDefinitions` query.
+    inputs: Map<String, Value>,
+}
+
+impl MetafieldDefinitionQueryBuilder {
+    fn new(query_type: MetafieldDefinitionQueryType, inputs: Map<String, Value>) -> Self {
+        Self { query_type, inputs }
+    }
+
+    /// Fetches the metafield definition with the given ID.
+    pub fn metafield_definition(id: Id<resource::MetafieldDefinition>) -> Self {
+        Self::new(
+            MetafieldDefinitionQueryType::MetafieldDefinition(id),
+            Map::new(),
+        )
+    }
+
+    /// Fetches a page of the definitions of metafields on `owner_type`
+    /// objects.
+    ///
+    /// Run it with [`build_connection`](Self::build_connection) or
+    /// [`stream`](Self::stream).
+    pub fn metafield_definitions(owner_type: MetafieldOwnerType, conn: ProductsConnection) -> Self {
+        Self::new(
+            MetafieldDefinitionQueryType::MetafieldDefinitions(owner_type, conn),
+            Map::new(),
+        )
+    }
+
+    /// Only returns the definitions in `namespace`.
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.inputs.insert("namespace".into(), namespace.into());
+        self
+    }
+
+    /// Creates the declared definition and returns it.
+    pub fn metafield_definition_create(definition: &MetafieldDefinitionInput) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("definition".into(), definition.create_input().into());
+
+        Self::new(
+            MetafieldDefinitionQueryType::MetafieldDefinitionCreate,
+            inputs,
+        )
+    }
+
+    /// Updates the definition with the declared owner type, namespace and key
+    /// to match the declaration, and returns it.
+    pub fn metafield_definition_update(definition: &MetafieldDefinitionInput) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("definition".into(), definition.update_input());
+
+        Self::new(
+            MetafieldDefinitionQueryType::MetafieldDefinitionUpdate,
+            inputs,
+        )
+    }
+
+    /// Deletes the definition with the given ID, and with
+    /// `delete_all_associated_metafields` set, every metafield that uses it.
+    ///
+    /// Only the `id` of the returned definition is set.
+    pub fn metafield_definition_delete(
+        id: Id<resource::MetafieldDefinition>,
+        delete_all_associated_metafields: bool,
+    ) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("id".into(), json!(id));
+        inputs.insert(
+            "deleteAllAssociatedMetafields".into(),
+            delete_all_associated_metafields.into(),
+        );
+
+        Self::new(
+            MetafieldDefinitionQueryType::MetafieldDefinitionDelete,
+            inputs,
+        )
+    }
+
+    /// Pins the definition with the given ID and returns it.
+    pub fn metafield_definition_pin(id: Id<resource::MetafieldDefinition>) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("definitionId".into(), json!(id));
+
+        Self::new(MetafieldDefinitionQueryType::MetafieldDefinitionPin, inputs)
+    }
+
+    /// Unpins the definition with the given ID and returns it.
+    pub fn metafield_definition_unpin(id: Id<resource::MetafieldDefinition>) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("definitionId".into(), json!(id));
+
+        Self::new(
+            MetafieldDefinitionQueryType::MetafieldDefinitionUnpin,
+            inputs,
+        )
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let fields = METAFIELD_DEFINITION_FIELDS;
+
+        match &self.query_type {
+            MetafieldDefinitionQueryType::MetafieldDefinition(id) => GraphQlRequest::new(format!(
+                "query metafieldDefinition($id: ID!) {{ metafieldDefinition(id: $id) {{ {} }} }}",
+                fields
+            ))
+            .with_variable("id", id.inner().clone()),
+
+            MetafieldDefinitionQueryType::MetafieldDefinitions(owner_type, conn) => GraphQlRequest::new(format!(
+                "query metafieldDefinitions({}, $ownerType: MetafieldOwnerType!, $namespace: String) {{ {} }}",
+                CONNECTION_VARIABLES,
+                connection_query(
+                    "metafieldDefinitions",
+                    &format!(
+                        "{}, ownerType: $ownerType, namespace: $namespace",
+                        CONNECTION_ARGUMENTS
+                    ),
+                    fields
+                )
+            ))
+            .with_variables(conn.variables())
+            .with_variable("ownerType", json!(owner_type))
+            .with_variables(self.inputs.clone()),
+
+            MetafieldDefinitionQueryType::MetafieldDefinitionCreate => GraphQlRequest::new(format!(
+                "mutation metafieldDefinitionCreate($definition: MetafieldDefinitionInput!) {{ metafieldDefinitionCreate(definition: $definition) {{ createdDefinition {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+
+            MetafieldDefinitionQueryType::MetafieldDefinitionUpdate => GraphQlRequest::new(format!(
+                "mutation metafieldDefinitionUpdate($definition: MetafieldDefinitionUpdateInput!) {{ metafieldDefinitionUpdate(definition: $definition) {{ updatedDefinition {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+
+            MetafieldDefinitionQueryType::MetafieldDefinitionDelete => GraphQlRequest::new(
+                "mutation metafieldDefinitionDelete($id: ID!, $deleteAllAssociatedMetafields: Boolean) { metafieldDefinitionDelete(id: $id, deleteAllAssociatedMetafields: $deleteAllAssociatedMetafields) { deletedDefinitionId userErrors { field message code } } }",
+            )
+            .with_variables(self.inputs.clone()),
+
+            MetafieldDefinitionQueryType::MetafieldDefinitionPin => GraphQlRequest::new(format!(
+                "mutation metafieldDefinitionPin($definitionId: ID!) {{ metafieldDefinitionPin(definitionId: $definitionId) {{ pinnedDefinition {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+
+            MetafieldDefinitionQueryType::MetafieldDefinitionUnpin => GraphQlRequest::new(format!(
+                "mutation metafieldDefinitionUnpin($definitionId: ID!) {{ metafieldDefinitionUnpin(definitionId: $definitionId) {{ unpinnedDefinition {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+        }
+    }
+
+    /// Sends the query through `client` and returns the definition.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<MetafieldDefinition> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::MetafieldDefinition(d) => Ok(d),
+
+            ResponseTypes::MetafieldDefinitionCreate {
+                created_definition: definition,
+                user_errors,
+            }
+            | ResponseTypes::MetafieldDefinitionUpdate {
+                updated_definition: definition,
+                user_errors,
+            }
+            | ResponseTypes::MetafieldDefinitionPin {
+                pinned_definition: definition,
+                user_errors,
+            }
+            | ResponseTypes::MetafieldDefinitionUnpin {
+                unpinned_definition: definition,
+                user_errors,
+            } => mutation_payload(definition, user_errors),
+
+            ResponseTypes::MetafieldDefinitionDelete {
+                deleted_definition_id,
+                user_errors,
+            } => {
+                mutation_payload(deleted_definition_id, user_errors).map(|id| MetafieldDefinition {
+                    id,
+                    ..MetafieldDefinition::default()
+                })
+            }
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Sends a [`metafield_definitions`](Self::metafield_definitions) query
+    /// through `client` and returns the page of definitions.
+    pub async fn build_connection(
+        self,
+        client: &ShopifyClient,
+    ) -> ShopifyResult<Edges<MetafieldDefinition>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::MetafieldDefinitions(d) => Ok(d),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Runs a [`metafield_definitions`](Self::metafield_definitions) query
+    /// page by page, yielding every definition until the connection is
+    /// exhausted.
+    ///
+    /// Yields a single [`ShopifyGqlError::InvalidQuery`] if the builder was
+    /// not created with [`metafield_definitions`](Self::metafield_definitions).
+    pub fn stream(
+        self,
+        client: &ShopifyClient,
+    ) -> impl Stream<Item = ShopifyResult<MetafieldDefinition>> {
+        let first = match &self.query_type {
+            MetafieldDefinitionQueryType::MetafieldDefinitions(_, conn) => Ok(conn.clone()),
+            _ => Err(ShopifyGqlError::InvalidQuery(
+                "`stream` requires a `MetafieldDefinitionQueryBuilder::metafield_definitions` query"
+                    .into(),
+            )),
+        };
+
+        stream_connection(
+            self,
+            first,
+            client,
+            |page, conn| {
+                if let MetafieldDefinitionQueryType::MetafieldDefinitions(_, page_conn) =
+                    &mut page.query_type
+                {
+                    *page_conn = conn;
+                }
+            },
+            |page, client| async move { page.build_connection(&client).await },
+        )
+    }
+}
+
+/// The custom object type that metaobjects are entries of, such as
+/// `designer` with `name` and `bio` fields.
+///
+/// Every field is fetched, so they are only `None` on the result of a
+/// [`metaobject_definition_delete`](MetaobjectDefinitionQueryBuilder::metaobject_definition_delete).
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MetaobjectDefinition {
+    id: Id<resource::MetaobjectDefinition>,
+
+    #[serde(rename = "type")]
+    type_name: Option<String>,
+
+    name: Option<String>,
+    description: Option<String>,
+    display_name_key: Option<String>,
+    field_definitions: Option<Vec<MetaobjectFieldDefinition>>,
+}
+
+impl MetaobjectDefinition {
+    pub fn id(&self) -> &Id<resource::MetaobjectDefinition> {
+        &self.id
+    }
+
+    /// The type of the metaobjects, such as `designer`, which identifies the
+    /// definition.
+    pub fn type_name(&self) -> Option<&String> {
+        self.type_name.as_ref()
+    }
+
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    /// The key of the field used as the display name of each metaobject.
+    pub fn display_name_key(&self) -> Option<&String> {
+        self.display_name_key.as_ref()
+    }
+
+    pub fn field_definitions(&self) -> Option<&[MetaobjectFieldDefinition]> {
+        self.field_definitions.as_deref()
+    }
+
+    /// Returns the definition of the field with the given key.
+    pub fn field_definition(&self, key: &str) -> Option<&MetaobjectFieldDefinition> {
+        self.field_definitions
+            .as_ref()?
+            .iter()
+            .find(|f| f.key == key)
+    }
+}
+
+/// A field of a [`MetaobjectDefinition`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MetaobjectFieldDefinition {
+    key: String,
+    name: Option<String>,
+    description: Option<String>,
+    required: bool,
+
+    #[serde(rename = "type")]
+    field_type: DefinitionType,
+
+    validations: Vec<DefinitionValidation>,
+}
+
+impl MetaobjectFieldDefinition {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    pub fn required(&self) -> bool {
+        self.required
+    }
+
+    /// The type of the field's values, such as `single_line_text_field`.
+    pub fn type_name(&self) -> &str {
+        &self.field_type.name
+    }
+
+    pub fn validations(&self) -> &[DefinitionValidation] {
+        &self.validations
+    }
+}
+
+/// Declares a field of a [`MetaobjectDefinitionInput`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct MetaobjectFieldDefinitionInput {
+    key: String,
+    name: String,
+    type_name: String,
+    description: Option<String>,
+    required: bool,
+    validations: Vec<DefinitionValidation>,
+}
+
+impl MetaobjectFieldDefinitionInput {
+    /// A field called `key`, whose values have type `type_name`.
+    pub fn new(key: &str, name: &str, type_name: &str) -> Self {
+        Self {
+            key: key.into(),
+            name: name.into(),
+            type_name: type_name.into(),
+            description: None,
+            required: false,
+            validations: vec![],
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Makes every metaobject have a value for the field.
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn validation(mut self, validation: DefinitionValidation) -> Self {
+        self.validations.push(validation);
+        self
+    }
+
+    /// Returns `true` if `field` is the declared one, or an error if it has
+    /// another type, which a definition update can't change.
+    fn is_applied_to(&self, field: &MetaobjectFieldDefinition) -> ShopifyResult<bool> {
+        if field.type_name() != self.type_name {
+            return Err(ShopifyGqlError::DefinitionTypeChanged {
+                key: self.key.clone(),
+                existing: field.type_name().into(),
+                declared: self.type_name.clone(),
+            });
+        }
+
+        Ok(field.name.as_ref() == Some(&self.name)
+            && field.description.as_deref().unwrap_or_default()
+                == self.description.as_deref().unwrap_or_default()
+            && field.required == self.required
+            && field.validations == self.validations)
+    }
+
+    /// The `MetaobjectFieldDefinitionCreateInput`, or the
+    /// `MetaobjectFieldDefinitionUpdateInput` without `type`, which can't be
+    /// changed.
+    fn input(&self, with_type: bool) -> Value {
+        let mut input = Map::new();
+        input.insert("key".into(), self.key.clone().into());
+        input.insert("name".into(), self.name.clone().into());
+        input.insert("required".into(), self.required.into());
+        input.insert("validations".into(), json!(self.validations));
+
+        if let Some(description) = &self.description {
+            input.insert("description".into(), description.clone().into());
+        }
+        if with_type {
+            input.insert("type".into(), self.type_name.clone().into());
+        }
+
+        input.into()
+    }
+}
+
+/// Declares a metaobject definition, to create it or bring an existing one up
+/// to date.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MetaobjectDefinitionInput {
+    type_name: String,
+    name: String,
+    description: Option<String>,
+    display_name_key: Option<String>,
+    field_definitions: Vec<MetaobjectFieldDefinitionInput>,
+}
+
+impl MetaobjectDefinitionInput {
+    /// A definition of the metaobjects of type `type_name`, such as
+    /// `designer`.
+    pub fn new(type_name: &str, name: &str) -> Self {
+        Self {
+            type_name: type_name.into(),
+            name: name.into(),
+            description: None,
+            display_name_key: None,
+            field_definitions: vec![],
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Uses the field called `key` as the display name of each metaobject.
+    pub fn display_name_key(mut self, key: &str) -> Self {
+        self.display_name_key = Some(key.into());
+        self
+    }
+
+    pub fn field(mut self, field: MetaobjectFieldDefinitionInput) -> Self {
+        self.field_definitions.push(field);
+        self
+    }
+
+    /// Returns the changes that bring `definition` up to date with the
+    /// declaration, or `None` if it already is.
+    ///
+    /// Declared fields that are missing are created and the others updated.
+    /// Fields that aren't declared are left alone, since deleting a field
+    /// deletes its values; remove them with
+    /// [`MetaobjectDefinitionUpdate::delete_field`].
+    ///
+    /// Returns [`ShopifyGqlError::DefinitionTypeChanged`] if a declared field
+    /// exists with another type, as field types can't be updated.
+    pub fn update_for(
+        &self,
+        definition: &MetaobjectDefinition,
+    ) -> ShopifyResult<Option<MetaobjectDefinitionUpdate>> {
+        let mut update = MetaobjectDefinitionUpdate::new();
+        let mut changed = false;
+
+        if definition.name.as_ref() != Some(&self.name) {
+            update = update.name(&self.name);
+            changed = true;
+        }
+        if definition.description.as_deref().unwrap_or_default()
+            != self.description.as_deref().unwrap_or_default()
+        {
+            update = update.description(self.description.as_deref().unwrap_or_default());
+            changed = true;
+        }
+        if let Some(key) = &self.display_name_key {
+            if definition.display_name_key.as_ref() != Some(key) {
+                update = update.display_name_key(key);
+                changed = true;
+            }
+        }
+
+        for field in &self.field_definitions {
+            match definition.field_definition(&field.key) {
+                Some(existing) => {
+                    if !field.is_applied_to(existing)? {
+                        update = update.update_field(field.clone());
+                        changed = true;
+                    }
+                }
+                None => {
+                    update = update.create_field(field.clone());
+                    changed = true;
+                }
+            }
+        }
+
+        Ok(changed.then_some(update))
+    }
+
+    /// The `MetaobjectDefinitionCreateInput`.
+    fn create_input(&self) -> Value {
+        let mut input = Map::new();
+        input.insert("type".into(), self.type_name.clone().into());
+        input.insert("name".into(), self.name.clone().into());
+        input.insert(
+            "fieldDefinitions".into(),
+            self.field_definitions
+                .iter()
+                .map(|f| f.input(true))
+                .collect(),
+        );
+
+        if let Some(description) = &self.description {
+            input.insert("description".into(), description.clone().into());
+        }
+        if let Some(key) = &self.display_name_key {
+            input.insert("displayNameKey".into(), key.clone().into());
+        }
+
+        input.into()
+    }
+}
+
+/// Changes to an existing metaobject definition, for
+/// [`MetaobjectDefinitionQueryBuilder::metaobject_definition_update`].
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MetaobjectDefinitionUpdate {
+    definition: Map<String, Value>,
+    field_definitions: Vec<Value>,
+}
+
+impl MetaobjectDefinitionUpdate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.definition.insert("name".into(), name.into());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.definition
+            .insert("description".into(), description.into());
+        self
+    }
+
+    pub fn display_name_key(mut self, key: &str) -> Self {
+        self.definition.insert("displayNameKey".into(), key.into());
+        self
+    }
+
+    /// Adds a field to the definition.
+    pub fn create_field(mut self, field: MetaobjectFieldDefinitionInput) -> Self {
+        self.field_definitions
+            .push(json!({ "create": field.input(true) }));
+        self
+    }
+
+    /// Changes the field with the same key. Its type can't be changed.
+    pub fn update_field(mut self, field: MetaobjectFieldDefinitionInput) -> Self {
+        self.field_definitions
+            .push(json!({ "update": field.input(false) }));
+        self
+    }
+
+    /// Removes the field called `key`, along with its values on every
+    /// metaobject.
+    pub fn delete_field(mut self, key: &str) -> Self {
+        self.field_definitions
+            .push(json!({ "delete": { "key": key } }));
+        self
+    }
+
+    /// The `MetaobjectDefinitionUpdateInput`.
+    fn input(&self) -> Value {
+        let mut input = self.definition.clone();
+        if !self.field_definitions.is_empty() {
+            input.insert(
+                "fieldDefinitions".into(),
+                self.field_definitions.clone().into(),
+            );
+        }
+
+        input.into()
+    }
+}
+
+/// All possible queries and mutations on a `MetaobjectDefinition`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum MetaobjectDefinitionQueryType {
+    MetaobjectDefinition(Id<resource::MetaobjectDefinition>),
+    MetaobjectDefinitionByType(String),
+    MetaobjectDefinitions(ProductsConnection),
+    MetaobjectDefinitionCreate,
+    MetaobjectDefinitionUpdate,
+    MetaobjectDefinitionDelete,
+}
+
+/// Builds and runs a query or mutation that returns a
+/// [`MetaobjectDefinition`].
+///
+/// Every field of the definitions is always fetched.
+#[derive(Debug, Clone)]
+pub struct MetaobjectDefinitionQueryBuilder {
+    query_type: MetaobjectDefinitionQueryType,
+
+    /// The variables of a mutation.
+    inputs: Map<String, Value>,
+}
+
+impl MetaobjectDefinitionQueryBuilder {
+    fn new(query_type: MetaobjectDefinitionQueryType, inputs: Map<String, Value>) -> Self {
+        Self { query_type, inputs }
+    }
+
+    /// Fetches the metaobject definition with the given ID.
+    pub fn metaobject_definition(id: Id<resource::MetaobjectDefinition>) -> Self {
+        Self::new(
+            MetaobjectDefinitionQueryType::MetaobjectDefinition(id),
+            Map::new(),
+        )
+    }
+
+    /// Fetches the metaobject definition of the given type, such as
+    /// `designer`.
+    pub fn metaobject_definition_by_type(type_name: &str) -> Self {
+        Self::new(
+            MetaobjectDefinitionQueryType::MetaobjectDefinitionByType(type_name.into()),
+            Map::new(),
+        )
+    }
+
+    /// Fetches a page of the store's metaobject definitions.
+    ///
+    /// Run it with [`build_connection`](Self::build_connection) or
+    /// [`stream`](Self::stream).
+    pub fn metaobject_definitions(conn: ProductsConnection) -> Self {
+        Self::new(
+            MetaobjectDefinitionQueryType::MetaobjectDefinitions(conn),
+            Map::new(),
+        )
+    }
+
+    /// Creates the declared definition and returns it.
+    pub fn metaobject_definition_create(definition: &MetaobjectDefinitionInput) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("definition".into(), definition.create_input());
+
+        Self::new(
+            MetaobjectDefinitionQueryType::MetaobjectDefinitionCreate,
+            inputs,
+        )
+    }
+
+    /// Applies `update` to the definition with the given ID and returns it.
+    pub fn metaobject_definition_update(
+        id: Id<resource::MetaobjectDefinition>,
+        update: MetaobjectDefinitionUpdate,
+    ) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("id".into(), json!(id));
+        inputs.insert("definition".into(), update.input());
+
+        Self::new(
+            MetaobjectDefinitionQueryType::MetaobjectDefinitionUpdate,
+            inputs,
+        )
+    }
+
+    /// Deletes the definition with the given ID, along with all of its
+    /// metaobjects.
+    ///
+    /// Only the `id` of the returned definition is set.
+    pub fn metaobject_definition_delete(id: Id<resource::MetaobjectDefinition>) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("id".into(), json!(id));
+
+        Self::new(
+            MetaobjectDefinitionQueryType::MetaobjectDefinitionDelete,
+            inputs,
+        )
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let fields = METAOBJECT_DEFINITION_FIELDS;
+
+        match &self.query_type {
+            MetaobjectDefinitionQueryType::MetaobjectDefinition(id) => GraphQlRequest::new(format!(
+                "query metaobjectDefinition($id: ID!) {{ metaobjectDefinition(id: $id) {{ {} }} }}",
+                fields
+            ))
+            .with_variable("id", id.inner().clone()),
+
+            MetaobjectDefinitionQueryType::MetaobjectDefinitionByType(type_name) => GraphQlRequest::new(format!(
+                "query metaobjectDefinitionByType($type: String!) {{ metaobjectDefinitionByType(type: $type) {{ {} }} }}",
+                fields
+            ))
+            .with_variable("type", type_name.clone()),
+
+            MetaobjectDefinitionQueryType::MetaobjectDefinitions(conn) => GraphQlRequest::new(format!(
+                "query metaobjectDefinitions({}) {{ {} }}",
+                CONNECTION_VARIABLES,
+                connection_query("metaobjectDefinitions", CONNECTION_ARGUMENTS, fields)
+            ))
+            .with_variables(conn.variables()),
+
+            MetaobjectDefinitionQueryType::MetaobjectDefinitionCreate => GraphQlRequest::new(format!(
+                "mutation metaobjectDefinitionCreate($definition: MetaobjectDefinitionCreateInput!) {{ metaobjectDefinitionCreate(definition: $definition) {{ metaobjectDefinition {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+
+            MetaobjectDefinitionQueryType::MetaobjectDefinitionUpdate => GraphQlRequest::new(format!(
+                "mutation metaobjectDefinitionUpdate($id: ID!, $definition: MetaobjectDefinitionUpdateInput!) {{ metaobjectDefinitionUpdate(id: $id, definition: $definition) {{ metaobjectDefinition {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+
+            MetaobjectDefinitionQueryType::MetaobjectDefinitionDelete => GraphQlRequest::new(
+                "mutation metaobjectDefinitionDelete($id: ID!) { metaobjectDefinitionDelete(id: $id) { deletedId userErrors { field message code } } }",
+            )
+            .with_variables(self.inputs.clone()),
+        }
+    }
+
+    /// Sends the query through `client` and returns the definition.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<MetaobjectDefinition> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::MetaobjectDefinition(d) => Ok(d),
+
+            // Unlike `metaobjectDefinition`, this returns null for an unknown
+            // type.
+            ResponseTypes::MetaobjectDefinitionByType(d) => d.ok_or_else(|| {
+                ShopifyGqlError::ResponseError("No metaobject definition has the given type".into())
+            }),
+
+            ResponseTypes::MetaobjectDefinitionCreate {
+                metaobject_definition,
+                user_errors,
+            }
+            | ResponseTypes::MetaobjectDefinitionUpdate {
+                metaobject_definition,
+                user_errors,
+            } => mutation_payload(metaobject_definition, user_errors),
+
+            ResponseTypes::MetaobjectDefinitionDelete {
+                deleted_id,
+                user_errors,
+            } => mutation_payload(deleted_id, user_errors).map(|id| MetaobjectDefinition {
+                id,
+                ..MetaobjectDefinition::default()
+            }),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Sends a [`metaobject_definitions`](Self::metaobject_definitions) query
+    /// through `client` and returns the page of definitions.
+    pub async fn build_connection(
+        self,
+        client: &ShopifyClient,
+    ) -> ShopifyResult<Edges<MetaobjectDefinition>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::MetaobjectDefinitions(d) => Ok(d),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Runs a [`metaobject_definitions`](Self::metaobject_definitions) query
+    /// page by page, yielding every definition until the connection is
+    /// exhausted.
+    ///
+    /// Yields a single [`ShopifyGqlError::InvalidQuery`] if the builder was
+    /// not created with [`metaobject_definitions`](Self::metaobject_definitions).
+    pub fn stream(
+        self,
+        client: &ShopifyClient,
+    ) -> impl Stream<Item = ShopifyResult<MetaobjectDefinition>> {
+        let first = match &self.query_type {
+            MetaobjectDefinitionQueryType::MetaobjectDefinitions(conn) => Ok(conn.clone()),
+            _ => Err(ShopifyGqlError::InvalidQuery(
+                "`stream` requires a `MetaobjectDefinitionQueryBuilder::metaobject_definitions` query"
+                    .into(),
+            )),
+        };
+
+        stream_connection(
+            self,
+            first,
+            client,
+            move |page, conn| {
+                page.query_type = MetaobjectDefinitionQueryType::MetaobjectDefinitions(conn)
+            },
+            |page, client| async move { page.build_connection(&client).await },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn care_guide() -> MetafieldDefinitionInput {
+        MetafieldDefinitionInput::new(
+            MetafieldOwnerType::PRODUCT,
+            "custom",
+            "care_guide",
+            "Care guide",
+            "multi_line_text_field",
+        )
+        .validation(DefinitionValidation::new("max", "500"))
+        .pin()
+    }
+
+    #[test]
+    fn leaves_the_type_out_of_updates() {
+        let request =
+            MetafieldDefinitionQueryBuilder::metafield_definition_update(&care_guide()).request();
+
+        assert_eq!(
+            request.variables()["definition"],
+            json!({
+                "ownerType": "PRODUCT",
+                "namespace": "custom",
+                "key": "care_guide",
+                "name": "Care guide",
+                "validations": [{ "name": "max", "value": "500" }],
+                "pin": true
+            })
+        );
+    }
+
+    #[test]
+    fn compares_declared_metafield_definitions() {
+        let mut definition: MetafieldDefinition = serde_json::from_value(json!({
+            "id": "gid://shopify/MetafieldDefinition/1",
+            "name": "Care guide",
+            "namespace": "custom",
+            "key": "care_guide",
+            "description": null,
+            "ownerType": "PRODUCT",
+            "type": { "name": "multi_line_text_field" },
+            "validations": [{ "name": "max", "value": "500" }],
+            "pinnedPosition": 1
+        }))
+        .unwrap();
+
+        assert_eq!(definition.type_name(), Some("multi_line_text_field"));
+        assert!(care_guide().is_applied_to(&definition).unwrap());
+
+        definition.pinned_position = None;
+        assert!(!care_guide().is_applied_to(&definition).unwrap());
+
+        definition.definition_type = Some(DefinitionType {
+            name: "single_line_text_field".into(),
+        });
+        assert!(matches!(
+            care_guide().is_applied_to(&definition),
+            Err(ShopifyGqlError::DefinitionTypeChanged { existing, .. })
+                if existing == "single_line_text_field"
+        ));
+    }
+
+    #[test]
+    fn creates_and_updates_declared_metaobject_fields() {
+        let declared = MetaobjectDefinitionInput::new("designer", "Designer")
+            .display_name_key("name")
+            .field(
+                MetaobjectFieldDefinitionInput::new("name", "Name", "single_line_text_field")
+                    .required(),
+            )
+            .field(MetaobjectFieldDefinitionInput::new(
+                "bio",
+                "Biography",
+                "multi_line_text_field",
+            ));
+
+        let existing: MetaobjectDefinition = serde_json::from_value(json!({
+            "id": "gid://shopify/MetaobjectDefinition/1",
+            "type": "designer",
+            "name": "Designer",
+            "description": null,
+            "displayNameKey": "name",
+            "fieldDefinitions": [{
+                "key": "name",
+                "name": "Name",
+                "description": null,
+                "required": false,
+                "type": { "name": "single_line_text_field" },
+                "validations": []
+            }]
+        }))
+        .unwrap();
+
+        let update = declared.update_for(&existing).unwrap().unwrap();
+        assert_eq!(
+            update.input(),
+            json!({
+                "fieldDefinitions": [
+                    { "update": { "key": "name", "name": "Name", "required": true, "validations": [] } },
+                    { "create": {
+                        "key": "bio",
+                        "name": "Biography",
+                        "required": false,
+                        "validations": [],
+                        "type": "multi_line_text_field"
+                    } }
+                ]
+            })
+        );
+
+        let up_to_date = MetaobjectDefinitionInput::new("designer", "Designer").field(
+            MetaobjectFieldDefinitionInput::new("name", "Name", "single_line_text_field"),
+        );
+        assert_eq!(up_to_date.update_for(&existing).unwrap(), None);
+
+        let retyped = MetaobjectDefinitionInput::new("designer", "Designer").field(
+            MetaobjectFieldDefinitionInput::new("name", "Name", "multi_line_text_field"),
+        );
+        assert!(matches!(
+            retyped.update_for(&existing),
+            Err(ShopifyGqlError::DefinitionTypeChanged { key, .. }) if key == "name"
+        ));
+    }
+}
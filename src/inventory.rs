@@ -0,0 +1,674 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    common::{resource, Edges, Id},
+    products::{connection_query, ProductsConnection},
+    utils::{
+        mutation_payload, run_request, GraphQlRequest, ResponseTypes, ShopifyClient,
+        ShopifyGqlError, ShopifyResult, UserError,
+    },
+};
+
+/// The fields selected whenever a variant's `inventoryItem` is fetched.
+pub(crate) const INVENTORY_ITEM_FIELDS: &str = "inventoryItem { id sku tracked requiresShipping }";
+
+/// The fields selected for each inventory level, whose quantities are named
+/// by a `$names: [String!]!` variable.
+pub(crate) const INVENTORY_LEVEL_FIELDS: &str =
+    "id location { id } item { id } quantities(names: $names) { name quantity }";
+
+/// The fields of an `InventoryAdjustmentGroup` returned by the inventory
+/// mutations.
+const ADJUSTMENT_GROUP_FIELDS: &str = "inventoryAdjustmentGroup { id createdAt reason referenceDocumentUri changes { name delta quantityAfterChange item { id } location { id } } } userErrors { field message code }";
+
+/// The physical goods behind a product variant, whose stock is tracked per
+/// location.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryItem {
+    id: Id<resource::InventoryItem>,
+    sku: Option<String>,
+
+    /// Whether Shopify tracks the item's quantities.
+    tracked: bool,
+
+    requires_shipping: bool,
+
+    /// Only fetched by [`InventoryItemQueryBuilder::inventory_levels`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inventory_levels: Option<Edges<InventoryLevel>>,
+}
+
+impl InventoryItem {
+    pub fn id(&self) -> &Id<resource::InventoryItem> {
+        &self.id
+    }
+
+    pub fn sku(&self) -> Option<&String> {
+        self.sku.as_ref()
+    }
+
+    /// Returns `true` if Shopify tracks the item's quantities.
+    pub fn tracked(&self) -> bool {
+        self.tracked
+    }
+
+    pub fn requires_shipping(&self) -> bool {
+        self.requires_shipping
+    }
+
+    /// The fetched page of the item's stock at each location that stocks it.
+    pub fn inventory_levels(&self) -> Option<&Edges<InventoryLevel>> {
+        self.inventory_levels.as_ref()
+    }
+
+    /// The item's stock at the location with the given ID, if it is on the
+    /// fetched page of [`inventory_levels`](Self::inventory_levels).
+    pub fn inventory_level(&self, location_id: &Id<resource::Location>) -> Option<&InventoryLevel> {
+        self.inventory_levels
+            .as_ref()?
+            .iter()
+            .find(|level| level.location_id() == location_id)
+    }
+}
+
+/// The stock of an [`InventoryItem`] at one location.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryLevel {
+    id: Id<resource::InventoryLevel>,
+    location: LocationRef,
+    item: InventoryItemRef,
+    quantities: Vec<InventoryQuantity>,
+}
+
+impl InventoryLevel {
+    pub fn id(&self) -> &Id<resource::InventoryLevel> {
+        &self.id
+    }
+
+    pub fn location_id(&self) -> &Id<resource::Location> {
+        &self.location.id
+    }
+
+    pub fn inventory_item_id(&self) -> &Id<resource::InventoryItem> {
+        &self.item.id
+    }
+
+    /// The quantities that were requested with
+    /// [`InventoryItemQueryBuilder::inventory_levels`] or
+    /// [`LocationQueryBuilder::inventory_levels`](crate::locations::LocationQueryBuilder::inventory_levels).
+    pub fn quantities(&self) -> &[InventoryQuantity] {
+        &self.quantities
+    }
+
+    /// Returns the quantity called `name`, if it was fetched.
+    pub fn quantity(&self, name: InventoryQuantityName) -> Option<i32> {
+        self.quantities
+            .iter()
+            .find(|q| q.name == name)
+            .map(|q| q.quantity)
+    }
+}
+
+/// A location or inventory item that is only fetched as `{ id }`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+struct LocationRef {
+    id: Id<resource::Location>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+struct InventoryItemRef {
+    id: Id<resource::InventoryItem>,
+}
+
+/// The states that a unit of inventory can be in.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum InventoryQuantityName {
+    /// Units that can be sold.
+    Available,
+
+    /// Units that are part of unfulfilled orders.
+    Committed,
+
+    Damaged,
+
+    /// Units on their way to the location.
+    Incoming,
+
+    /// Every unit physically at the location: `available`, `committed`,
+    /// `damaged`, `quality_control`, `reserved` and `safety_stock` combined.
+    OnHand,
+
+    QualityControl,
+    Reserved,
+    SafetyStock,
+}
+
+/// One of the quantities of an [`InventoryLevel`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct InventoryQuantity {
+    name: InventoryQuantityName,
+    quantity: i32,
+}
+
+impl InventoryQuantity {
+    pub fn name(&self) -> InventoryQuantityName {
+        self.name
+    }
+
+    pub fn quantity(&self) -> i32 {
+        self.quantity
+    }
+}
+
+/// Why inventory quantities were changed, as recorded in the item's history.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum InventoryChangeReason {
+    Correction,
+    CycleCountAvailable,
+    Damaged,
+    MovementCanceled,
+    MovementCreated,
+    MovementReceived,
+    MovementUpdated,
+    Other,
+    Promotion,
+    QualityControl,
+    Received,
+    ReservationCreated,
+    ReservationDeleted,
+    ReservationUpdated,
+    Restock,
+    SafetyStock,
+    Shrinkage,
+}
+
+/// The record of one of the inventory mutations.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryAdjustmentGroup {
+    id: Id<resource::InventoryAdjustmentGroup>,
+    created_at: DateTime<Utc>,
+    reason: String,
+    reference_document_uri: Option<String>,
+    changes: Vec<InventoryChange>,
+}
+
+impl InventoryAdjustmentGroup {
+    pub fn id(&self) -> &Id<resource::InventoryAdjustmentGroup> {
+        &self.id
+    }
+
+    pub fn created_at(&self) -> &DateTime<Utc> {
+        &self.created_at
+    }
+
+    /// A description of the [`InventoryChangeReason`], such as `Correction`.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn reference_document_uri(&self) -> Option<&String> {
+        self.reference_document_uri.as_ref()
+    }
+
+    pub fn changes(&self) -> &[InventoryChange] {
+        &self.changes
+    }
+}
+
+/// The change to one quantity of one item at one location.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryChange {
+    name: InventoryQuantityName,
+    delta: i32,
+
+    /// `None` when Shopify hasn't applied the change yet.
+    quantity_after_change: Option<i32>,
+
+    item: InventoryItemRef,
+    location: LocationRef,
+}
+
+impl InventoryChange {
+    pub fn name(&self) -> InventoryQuantityName {
+        self.name
+    }
+
+    pub fn delta(&self) -> i32 {
+        self.delta
+    }
+
+    pub fn quantity_after_change(&self) -> Option<i32> {
+        self.quantity_after_change
+    }
+
+    pub fn inventory_item_id(&self) -> &Id<resource::InventoryItem> {
+        &self.item.id
+    }
+
+    pub fn location_id(&self) -> &Id<resource::Location> {
+        &self.location.id
+    }
+}
+
+/// Builds and runs a query that returns an [`InventoryItem`].
+#[derive(Debug, Clone)]
+pub struct InventoryItemQueryBuilder {
+    id: Id<resource::InventoryItem>,
+
+    /// The page of levels to fetch and the quantities to fetch for each, or
+    /// `None` to leave out the levels.
+    levels: Option<(ProductsConnection, Vec<InventoryQuantityName>)>,
+}
+
+impl InventoryItemQueryBuilder {
+    /// Fetches the inventory item with the given ID.
+    pub fn inventory_item(id: Id<resource::InventoryItem>) -> Self {
+        Self { id, levels: None }
+    }
+
+    /// Also fetches a page of the item's inventory levels, with the given
+    /// quantities.
+    pub fn inventory_levels(
+        mut self,
+        conn: ProductsConnection,
+        quantities: &[InventoryQuantityName],
+    ) -> Self {
+        self.levels = Some((conn, quantities.to_vec()));
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let mut fields = String::from("id sku tracked requiresShipping");
+
+        let request = match &self.levels {
+            Some((conn, names)) => {
+                fields.push(' ');
+                fields.push_str(&connection_query(
+                    "inventoryLevels",
+                    &conn.arguments(),
+                    INVENTORY_LEVEL_FIELDS,
+                ));
+
+                GraphQlRequest::new(format!(
+                    "query inventoryItem($id: ID!, $names: [String!]!) {{ inventoryItem(id: $id) {{ {} }} }}",
+                    fields
+                ))
+                .with_variable("names", json!(names))
+            }
+
+            None => GraphQlRequest::new(format!(
+                "query inventoryItem($id: ID!) {{ inventoryItem(id: $id) {{ {} }} }}",
+                fields
+            )),
+        };
+
+        request.with_variable("id", self.id.inner().clone())
+    }
+
+    /// Sends the query through `client` and returns the inventory item.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<InventoryItem> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+
+        match res {
+            ResponseTypes::InventoryItem(item) => Ok(item),
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+}
+
+/// Changes the `available` or another quantity of inventory items by a delta,
+/// with `inventoryAdjustQuantities`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InventoryAdjustQuantities {
+    input: Map<String, Value>,
+    changes: Vec<Value>,
+}
+
+impl InventoryAdjustQuantities {
+    /// Adjusts the quantity called `name`.
+    pub fn new(reason: InventoryChangeReason, name: InventoryQuantityName) -> Self {
+        let mut input = Map::new();
+        input.insert("reason".into(), json!(reason));
+        input.insert("name".into(), json!(name));
+
+        Self {
+            input,
+            changes: vec![],
+        }
+    }
+
+    /// A URI for the document behind the change, such as
+    /// `gid://my-app/PurchaseOrder/42`.
+    ///
+    /// Retrying with the same URI lets Shopify recognise the change as a
+    /// duplicate.
+    pub fn reference_document_uri(mut self, uri: &str) -> Self {
+        self.input.insert("referenceDocumentUri".into(), uri.into());
+        self
+    }
+
+    /// Adds `delta`, which may be negative, to the item's quantity at the
+    /// location.
+    pub fn change(
+        mut self,
+        inventory_item_id: Id<resource::InventoryItem>,
+        location_id: Id<resource::Location>,
+        delta: i32,
+    ) -> Self {
+        self.changes.push(json!({
+            "inventoryItemId": inventory_item_id,
+            "locationId": location_id,
+            "delta": delta,
+        }));
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let mut input = self.input.clone();
+        input.insert("changes".into(), self.changes.clone().into());
+
+        GraphQlRequest::new(format!(
+            "mutation inventoryAdjustQuantities($input: InventoryAdjustQuantitiesInput!) {{ inventoryAdjustQuantities(input: $input) {{ {} }} }}",
+            ADJUSTMENT_GROUP_FIELDS
+        ))
+        .with_variable("input", input)
+    }
+
+    /// Sends the mutation through `client` and returns the record of the
+    /// changes.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<InventoryAdjustmentGroup> {
+        run_adjustment(client, &self.request())
+            .await
+            .and_then(|(group, user_errors)| mutation_payload(group, user_errors))
+    }
+}
+
+/// Sets the `on_hand` quantity of inventory items, with
+/// `inventorySetOnHandQuantities`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InventorySetOnHandQuantities {
+    input: Map<String, Value>,
+    set_quantities: Vec<Value>,
+}
+
+impl InventorySetOnHandQuantities {
+    pub fn new(reason: InventoryChangeReason) -> Self {
+        let mut input = Map::new();
+        input.insert("reason".into(), json!(reason));
+
+        Self {
+            input,
+            set_quantities: vec![],
+        }
+    }
+
+    /// See [`InventoryAdjustQuantities::reference_document_uri`].
+    pub fn reference_document_uri(mut self, uri: &str) -> Self {
+        self.input.insert("referenceDocumentUri".into(), uri.into());
+        self
+    }
+
+    /// Sets the item's `on_hand` quantity at the location to `quantity`.
+    pub fn set_quantity(
+        mut self,
+        inventory_item_id: Id<resource::InventoryItem>,
+        location_id: Id<resource::Location>,
+        quantity: i32,
+    ) -> Self {
+        self.set_quantities.push(json!({
+            "inventoryItemId": inventory_item_id,
+            "locationId": location_id,
+            "quantity": quantity,
+        }));
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let mut input = self.input.clone();
+        input.insert("setQuantities".into(), self.set_quantities.clone().into());
+
+        GraphQlRequest::new(format!(
+            "mutation inventorySetOnHandQuantities($input: InventorySetOnHandQuantitiesInput!) {{ inventorySetOnHandQuantities(input: $input) {{ {} }} }}",
+            ADJUSTMENT_GROUP_FIELDS
+        ))
+        .with_variable("input", input)
+    }
+
+    /// Sends the mutation through `client` and returns the record of the
+    /// changes, or `None` if every quantity was already set.
+    pub async fn build(
+        self,
+        client: &ShopifyClient,
+    ) -> ShopifyResult<Option<InventoryAdjustmentGroup>> {
+        let (group, user_errors) = run_adjustment(client, &self.request()).await?;
+
+        if !user_errors.is_empty() {
+            return Err(ShopifyGqlError::UserErrors(user_errors));
+        }
+
+        Ok(group)
+    }
+}
+
+/// One end of an [`InventoryMoveQuantities`] change.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InventoryMoveLocation {
+    fields: Map<String, Value>,
+}
+
+impl InventoryMoveLocation {
+    /// The quantity called `name` at the location with the given ID.
+    pub fn new(location_id: Id<resource::Location>, name: InventoryQuantityName) -> Self {
+        let mut fields = Map::new();
+        fields.insert("locationId".into(), json!(location_id));
+        fields.insert("name".into(), json!(name));
+
+        Self { fields }
+    }
+
+    /// A URI for the document that accounts for the units, such as
+    /// `gid://my-app/Order/42`. Required for every quantity but `available`.
+    pub fn ledger_document_uri(mut self, uri: &str) -> Self {
+        self.fields.insert("ledgerDocumentUri".into(), uri.into());
+        self
+    }
+}
+
+/// Moves units of inventory items from one quantity to another, such as from
+/// `available` to `damaged`, with `inventoryMoveQuantities`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InventoryMoveQuantities {
+    input: Map<String, Value>,
+    changes: Vec<Value>,
+}
+
+impl InventoryMoveQuantities {
+    pub fn new(reason: InventoryChangeReason, reference_document_uri: &str) -> Self {
+        let mut input = Map::new();
+        input.insert("reason".into(), json!(reason));
+        input.insert("referenceDocumentUri".into(), reference_document_uri.into());
+
+        Self {
+            input,
+            changes: vec![],
+        }
+    }
+
+    /// Moves `quantity` units of the item from `from` to `to`.
+    pub fn change(
+        mut self,
+        inventory_item_id: Id<resource::InventoryItem>,
+        quantity: i32,
+        from: InventoryMoveLocation,
+        to: InventoryMoveLocation,
+    ) -> Self {
+        self.changes.push(json!({
+            "inventoryItemId": inventory_item_id,
+            "quantity": quantity,
+            "from": from.fields,
+            "to": to.fields,
+        }));
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let mut input = self.input.clone();
+        input.insert("changes".into(), self.changes.clone().into());
+
+        GraphQlRequest::new(format!(
+            "mutation inventoryMoveQuantities($input: InventoryMoveQuantitiesInput!) {{ inventoryMoveQuantities(input: $input) {{ {} }} }}",
+            ADJUSTMENT_GROUP_FIELDS
+        ))
+        .with_variable("input", input)
+    }
+
+    /// Sends the mutation through `client` and returns the record of the
+    /// changes.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<InventoryAdjustmentGroup> {
+        run_adjustment(client, &self.request())
+            .await
+            .and_then(|(group, user_errors)| mutation_payload(group, user_errors))
+    }
+}
+
+/// Runs one of the inventory mutations, which all return an
+/// `inventoryAdjustmentGroup`.
+async fn run_adjustment(
+    client: &ShopifyClient,
+    request: &GraphQlRequest,
+) -> ShopifyResult<(Option<InventoryAdjustmentGroup>, Vec<UserError>)> {
+    let res = run_request(client, request).await?.into_data()?;
+
+    match res {
+        ResponseTypes::InventoryAdjustQuantities {
+            inventory_adjustment_group,
+            user_errors,
+        }
+        | ResponseTypes::InventorySetOnHandQuantities {
+            inventory_adjustment_group,
+            user_errors,
+        }
+        | ResponseTypes::InventoryMoveQuantities {
+            inventory_adjustment_group,
+            user_errors,
+        } => Ok((inventory_adjustment_group, user_errors)),
+
+        _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fetches_requested_quantities() {
+        let request = InventoryItemQueryBuilder::inventory_item(Id::from_legacy_id("7").unwrap())
+            .inventory_levels(
+                ProductsConnection::First(10),
+                &[
+                    InventoryQuantityName::Available,
+                    InventoryQuantityName::OnHand,
+                ],
+            )
+            .request();
+
+        assert!(request.query().contains("inventoryLevels(first: 10)"));
+        assert!(request.query().contains("quantities(names: $names)"));
+        assert_eq!(
+            request.variables()["names"],
+            json!(["available", "on_hand"])
+        );
+
+        let item: InventoryItem = serde_json::from_value(json!({
+            "id": "gid://shopify/InventoryItem/7",
+            "sku": null,
+            "tracked": true,
+            "requiresShipping": true,
+            "inventoryLevels": {
+                "edges": [{
+                    "cursor": "a",
+                    "node": {
+                        "id": "gid://shopify/InventoryLevel/9?inventory_item_id=7",
+                        "location": { "id": "gid://shopify/Location/3" },
+                        "item": { "id": "gid://shopify/InventoryItem/7" },
+                        "quantities": [
+                            { "name": "available", "quantity": 4 },
+                            { "name": "on_hand", "quantity": 6 }
+                        ]
+                    }
+                }],
+                "pageInfo": { "hasNextPage": true, "hasPreviousPage": false, "startCursor": "a", "endCursor": "a" }
+            }
+        }))
+        .unwrap();
+
+        let levels = item.inventory_levels().unwrap();
+        assert!(levels.page_info().unwrap().has_next_page());
+
+        let level = item.inventory_level(&Id::location("3").unwrap()).unwrap();
+        assert_eq!(level.quantity(InventoryQuantityName::OnHand), Some(6));
+        assert_eq!(level.quantity(InventoryQuantityName::Committed), None);
+    }
+
+    #[test]
+    fn passes_adjustments_as_input() {
+        let request = InventoryAdjustQuantities::new(
+            InventoryChangeReason::Correction,
+            InventoryQuantityName::Available,
+        )
+        .reference_document_uri("gid://my-app/Count/1")
+        .change(
+            Id::from_legacy_id("7").unwrap(),
+            Id::location("3").unwrap(),
+            -2,
+        )
+        .request();
+
+        assert_eq!(
+            request.variables()["input"],
+            json!({
+                "reason": "correction",
+                "name": "available",
+                "referenceDocumentUri": "gid://my-app/Count/1",
+                "changes": [{
+                    "inventoryItemId": "gid://shopify/InventoryItem/7",
+                    "locationId": "gid://shopify/Location/3",
+                    "delta": -2
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn passes_moves_as_input() {
+        let location = Id::location("3").unwrap();
+        let request =
+            InventoryMoveQuantities::new(InventoryChangeReason::Damaged, "gid://my-app/Report/1")
+                .change(
+                    Id::from_legacy_id("7").unwrap(),
+                    1,
+                    InventoryMoveLocation::new(location.clone(), InventoryQuantityName::Available),
+                    InventoryMoveLocation::new(location, InventoryQuantityName::Damaged)
+                        .ledger_document_uri("gid://my-app/Report/1"),
+                )
+                .request();
+
+        assert_eq!(
+            request.variables()["input"]["changes"][0]["to"],
+            json!({
+                "locationId": "gid://shopify/Location/3",
+                "name": "damaged",
+                "ledgerDocumentUri": "gid://my-app/Report/1"
+            })
+        );
+    }
+}
@@ -1,28 +1,173 @@
-mod products;
+//! A typed, builder-based client for the Shopify Admin GraphQL API.
+//!
+//! Queries are put together with builders such as [`ProductQueryBuilder`] and
+//! [`ProductVariantQueryBuilder`], which only request the fields that were
+//! selected, and are sent through a [`ShopifyClient`].
+//!
+//! ```no_run
+//! use shopify_gql::prelude::*;
+//!
+//! # async fn run() -> ShopifyResult<()> {
+//! let product = ProductQueryBuilder::product(Id::product("7343141159089")?)
+//!     .title()
+//!     .vendor()
+//!     .build(&ShopifyClient::from_env()?)
+//!     .await?;
+//!
+//! println!("{:?}", product.title());
+//! # Ok(())
+//! # }
+//! ```
 
-mod utils;
+pub mod products;
 
-mod common;
+pub mod utils;
 
-mod bulk_mutations;
+pub mod common;
+
+pub mod bulk_mutations;
+
+pub mod rate_limit;
+
+pub mod staged_uploads;
+
+pub mod inventory;
+
+pub mod locations;
+
+pub mod collections;
+
+pub mod metafields;
+
+pub mod definitions;
+
+pub mod metaobjects;
+
+pub use bulk_mutations::{
+    BulkMutation, BulkMutationReport, BulkMutationResult, BulkOperation, BulkOperationBuilder,
+    BulkQuery, BulkRecord, BulkResultReader,
+};
+pub use collections::{
+    Collection, CollectionQueryBuilder, CollectionRule, CollectionRuleColumn,
+    CollectionRuleRelation, CollectionRuleSet, CollectionSortKeys, CollectionSortOrder,
+};
+pub use common::{
+    resource, AnyResource, CurrencyCode, Edges, Id, Money, MoneyV2, Node, PageInfo, Resource, Seo,
+    UnknownCurrencyCode, WeightUnit,
+};
+pub use definitions::{
+    DefinitionValidation, MetafieldDefinition, MetafieldDefinitionInput,
+    MetafieldDefinitionQueryBuilder, MetafieldOwnerType, MetaobjectDefinition,
+    MetaobjectDefinitionInput, MetaobjectDefinitionQueryBuilder, MetaobjectDefinitionUpdate,
+    MetaobjectFieldDefinition, MetaobjectFieldDefinitionInput,
+};
+pub use inventory::{
+    InventoryAdjustQuantities, InventoryAdjustmentGroup, InventoryChange, InventoryChangeReason,
+    InventoryItem, InventoryItemQueryBuilder, InventoryLevel, InventoryMoveLocation,
+    InventoryMoveQuantities, InventoryQuantity, InventoryQuantityName,
+    InventorySetOnHandQuantities,
+};
+pub use locations::{Location, LocationAddress, LocationQueryBuilder};
+pub use metafields::{
+    Metafield, MetafieldIdentifier, MetafieldInput, MetafieldValue, MetafieldsDelete, MetafieldsSet,
+};
+pub use metaobjects::{
+    Metaobject, MetaobjectField, MetaobjectFieldInput, MetaobjectFields, MetaobjectInput,
+    MetaobjectQueryBuilder, MetaobjectStatus,
+};
+pub use products::{
+    media::{
+        stage_media, CreateMediaInput, Image, Media, MediaContentType, MediaHost,
+        MediaPreviewImage, MediaSource, MediaStatus, ProductCreateMedia, ProductDeleteMedia,
+        ProductReorderMedia, ProductUpdateMedia, ProductVariantAppendMedia, UpdateMediaInput,
+    },
+    product::{Product, ProductQueryBuilder, ProductSortKeys, ProductStatus},
+    product_option::{
+        OptionCreateInput, OptionUpdate, ProductOption, ProductOptionValue, SelectedOption,
+    },
+    product_variant::{
+        ProductVariant, ProductVariantInventoryPolicy, ProductVariantQueryBuilder, VariantInput,
+    },
+    ProductsConnection,
+};
+pub use rate_limit::{QueryCost, RateLimitOptions, ThrottleStatus};
+pub use rust_decimal::Decimal;
+pub use staged_uploads::{stage_upload, StagedUploadInput, StagedUploadTarget};
+pub use utils::{
+    run_query, run_request, GraphQlError, GraphQlRequest, QueryResponse, ShopifyClient,
+    ShopifyConfig, ShopifyGqlError, ShopifyResult, UserError,
+};
+
+/// Re-exports the types needed to build and run queries.
+///
+/// ```
+/// use shopify_gql::prelude::*;
+/// ```
+pub mod prelude {
+    pub use crate::collections::{
+        Collection, CollectionQueryBuilder, CollectionRule, CollectionRuleColumn,
+        CollectionRuleRelation, CollectionRuleSet, CollectionSortKeys, CollectionSortOrder,
+    };
+    pub use crate::common::{
+        resource, CurrencyCode, Edges, Id, Money, MoneyV2, Node, PageInfo, Seo, WeightUnit,
+    };
+    pub use crate::definitions::{
+        DefinitionValidation, MetafieldDefinition, MetafieldDefinitionInput,
+        MetafieldDefinitionQueryBuilder, MetafieldOwnerType, MetaobjectDefinition,
+        MetaobjectDefinitionInput, MetaobjectDefinitionQueryBuilder, MetaobjectDefinitionUpdate,
+        MetaobjectFieldDefinitionInput,
+    };
+    pub use crate::inventory::{
+        InventoryAdjustQuantities, InventoryChangeReason, InventoryItem, InventoryItemQueryBuilder,
+        InventoryLevel, InventoryMoveLocation, InventoryMoveQuantities, InventoryQuantityName,
+        InventorySetOnHandQuantities,
+    };
+    pub use crate::locations::{Location, LocationAddress, LocationQueryBuilder};
+    pub use crate::metafields::{
+        Metafield, MetafieldInput, MetafieldValue, MetafieldsDelete, MetafieldsSet,
+    };
+    pub use crate::metaobjects::{
+        Metaobject, MetaobjectFieldInput, MetaobjectFields, MetaobjectInput,
+        MetaobjectQueryBuilder, MetaobjectStatus,
+    };
+    pub use crate::products::{
+        media::{
+            stage_media, CreateMediaInput, Image, Media, MediaContentType, MediaStatus,
+            ProductCreateMedia, ProductDeleteMedia, ProductReorderMedia, ProductUpdateMedia,
+            ProductVariantAppendMedia, UpdateMediaInput,
+        },
+        product::{Product, ProductQueryBuilder, ProductSortKeys, ProductStatus},
+        product_option::{OptionCreateInput, OptionUpdate, ProductOption, SelectedOption},
+        product_variant::{
+            ProductVariant, ProductVariantInventoryPolicy, ProductVariantQueryBuilder, VariantInput,
+        },
+        ProductsConnection,
+    };
+    pub use crate::utils::{
+        GraphQlError, ShopifyClient, ShopifyConfig, ShopifyGqlError, ShopifyResult, UserError,
+    };
+    pub use rust_decimal::Decimal;
+}
 
 #[cfg(test)]
 mod tests {
     use crate::{
         common::{Id, Money, WeightUnit},
         products::{
-            product::{ProductQueryBuilder, ProductStatus},
+            product::{ProductQueryBuilder, ProductSortKeys, ProductStatus},
             product_variant::ProductVariantQueryBuilder,
             ProductsConnection,
         },
-        utils::{run_query, QueryResponse, ShopifyConfig, ShopifyResult},
+        utils::{run_query, QueryResponse, ShopifyClient, ShopifyResult},
     };
+    use futures::TryStreamExt;
+    use rust_decimal::Decimal;
     use std::any::{Any, TypeId};
 
     #[tokio::test]
     async fn test_connection() -> ShopifyResult<()> {
         let res = run_query(
-            ShopifyConfig::from_env()?,
+            &ShopifyClient::from_env()?,
             "query { product(id: \"gid://shopify/Product/7343141159089\") { id } }".into(),
         )
         .await?;
@@ -35,12 +180,12 @@ mod tests {
 
     #[tokio::test]
     async fn can_run_product_query() -> ShopifyResult<()> {
-        let config = ShopifyConfig::from_env()?;
+        let client = ShopifyClient::from_env()?;
         let prod = ProductQueryBuilder::product(Id::product("7343141159089")?)
             .status()
             .vendor()
             .title()
-            .build(config)
+            .build(&client)
             .await?;
 
         dbg!(&prod);
@@ -55,7 +200,7 @@ mod tests {
 
     #[tokio::test]
     async fn can_run_product_query_with_variants() -> ShopifyResult<()> {
-        let config = ShopifyConfig::from_env()?;
+        let client = ShopifyClient::from_env()?;
         let prod = ProductQueryBuilder::product(Id::product("7343141159089")?)
             .status()
             .vendor()
@@ -71,7 +216,7 @@ mod tests {
                     .weight()
                     .weight_unit(),
             )
-            .build(config)
+            .build(&client)
             .await?;
 
         dbg!(&prod);
@@ -83,9 +228,12 @@ mod tests {
 
         let var = prod.variants().unwrap().get_node(0);
         assert_eq!(var.id(), &Id::product_variant("42235355201713")?);
-        assert_eq!(var.compare_at_price(), Some(&Money(22.0)));
+        assert_eq!(
+            var.compare_at_price(),
+            Some(&Money::new(Decimal::new(22, 0)))
+        );
         assert_eq!(var.inventory_quantity(), Some(10));
-        assert_eq!(var.price(), Some(&Money(42.99)));
+        assert_eq!(var.price(), Some(&Money::new(Decimal::new(4299, 2))));
         assert_eq!(var.sku(), Some(&"12345-red".into()));
         assert_eq!(var.title(), Some(&"Red".into()));
         assert_eq!(var.weight(), Some(10.0));
@@ -94,15 +242,36 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn can_list_products() -> ShopifyResult<()> {
+        let client = ShopifyClient::from_env()?;
+        let products: Vec<_> = ProductQueryBuilder::products(ProductsConnection::First(1))
+            .search("vendor:TEST")
+            .sort_key(ProductSortKeys::TITLE)
+            .vendor()
+            .title()
+            .stream(&client)
+            .try_collect()
+            .await?;
+
+        dbg!(&products);
+
+        let id = Id::product("7343141159089")?;
+        assert!(products.iter().any(|p| p.id() == &id));
+        assert!(products.iter().all(|p| p.vendor().unwrap() == "TEST"));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn can_update_product() -> ShopifyResult<()> {
-        let config = ShopifyConfig::from_env()?;
+        let client = ShopifyClient::from_env()?;
 
         let prod = ProductQueryBuilder::product_update(Id::product("7343141159089")?)
             .update_title("MY TITLE")
             .update_vendor("TEST")
             .update_status(ProductStatus::DRAFT)
-            .build(config)
+            .build(&client)
             .await?;
 
         assert_eq!(prod.id(), &Id::product("7343141159089")?);
@@ -112,23 +281,23 @@ mod tests {
 
     #[tokio::test]
     async fn can_update_product_variant() -> ShopifyResult<()> {
-        let config = ShopifyConfig::from_env()?;
+        let client = ShopifyClient::from_env()?;
 
         let prod = ProductVariantQueryBuilder::product_variant_update(Id::product_variant(
             "42235355267249",
         )?)
         .update_sku("000000")
-        .update_price(Money(450.99))
+        .update_price(Money::new(Decimal::new(45099, 2)))
         .update_weight(100.)
         .update_weight_unit(WeightUnit::OUNCES)
-        .update_compare_at_price(Money(150.0))
+        .update_compare_at_price(Money::new(Decimal::new(150, 0)))
         .sku()
         .price()
         .weight()
         .weight_unit()
         .compare_at_price()
         .inventory_quantity()
-        .build(config)
+        .build(&client)
         .await?;
 
         assert_eq!(prod.id(), &Id::product_variant("42235355267249")?);
@@ -0,0 +1,364 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    common::{resource, stream_connection, Edges, Id},
+    inventory::{InventoryLevel, InventoryQuantityName, INVENTORY_LEVEL_FIELDS},
+    products::{connection_query, ProductsConnection, CONNECTION_ARGUMENTS, CONNECTION_VARIABLES},
+    utils::{
+        run_request, GraphQlRequest, ResponseTypes, ShopifyClient, ShopifyGqlError, ShopifyResult,
+    },
+};
+
+/// A place where inventory is stocked, such as a store or a warehouse.
+///
+/// Only the fields selected on the [`LocationQueryBuilder`] are populated; the
+/// rest are `None`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    id: Id<resource::Location>,
+    name: Option<String>,
+    address: Option<LocationAddress>,
+    is_active: Option<bool>,
+    fulfills_online_orders: Option<bool>,
+    inventory_levels: Option<Edges<InventoryLevel>>,
+}
+
+impl Location {
+    pub fn id(&self) -> &Id<resource::Location> {
+        &self.id
+    }
+
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn address(&self) -> Option<&LocationAddress> {
+        self.address.as_ref()
+    }
+
+    /// Returns `true` if the location can stock inventory and fulfill orders.
+    pub fn is_active(&self) -> Option<bool> {
+        self.is_active
+    }
+
+    pub fn fulfills_online_orders(&self) -> Option<bool> {
+        self.fulfills_online_orders
+    }
+
+    /// The fetched page of the stock of each inventory item at the location.
+    pub fn inventory_levels(&self) -> Option<&Edges<InventoryLevel>> {
+        self.inventory_levels.as_ref()
+    }
+}
+
+/// The address of a [`Location`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationAddress {
+    address1: Option<String>,
+    address2: Option<String>,
+    city: Option<String>,
+    province: Option<String>,
+    province_code: Option<String>,
+    country: Option<String>,
+    country_code: Option<String>,
+    zip: Option<String>,
+    phone: Option<String>,
+}
+
+impl LocationAddress {
+    pub fn address1(&self) -> Option<&String> {
+        self.address1.as_ref()
+    }
+
+    pub fn address2(&self) -> Option<&String> {
+        self.address2.as_ref()
+    }
+
+    pub fn city(&self) -> Option<&String> {
+        self.city.as_ref()
+    }
+
+    pub fn province(&self) -> Option<&String> {
+        self.province.as_ref()
+    }
+
+    /// The code of the province, such as `ON`.
+    pub fn province_code(&self) -> Option<&String> {
+        self.province_code.as_ref()
+    }
+
+    pub fn country(&self) -> Option<&String> {
+        self.country.as_ref()
+    }
+
+    /// The ISO 3166-1 alpha-2 code of the country, such as `CA`.
+    pub fn country_code(&self) -> Option<&String> {
+        self.country_code.as_ref()
+    }
+
+    pub fn zip(&self) -> Option<&String> {
+        self.zip.as_ref()
+    }
+
+    pub fn phone(&self) -> Option<&String> {
+        self.phone.as_ref()
+    }
+}
+
+/// All possible queries on a `Location`.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum LocationQueryType {
+    Location,
+    Locations(ProductsConnection),
+}
+
+/// Builds and runs a query that returns a [`Location`].
+#[derive(Debug, Clone)]
+pub struct LocationQueryBuilder {
+    id: Id<resource::Location>,
+    fields: HashMap<String, PhantomData<u8>>,
+    query_type: LocationQueryType,
+
+    /// Whether a `locations` query also returns deactivated locations.
+    include_inactive: bool,
+
+    /// The page of inventory levels to fetch and the quantities to fetch for
+    /// each, or `None` to leave out the levels.
+    levels: Option<(ProductsConnection, Vec<InventoryQuantityName>)>,
+}
+
+impl LocationQueryBuilder {
+    fn new(id: Id<resource::Location>, query_type: LocationQueryType) -> Self {
+        let mut fields = HashMap::new();
+        fields.insert("id".into(), PhantomData);
+
+        LocationQueryBuilder {
+            id,
+            fields,
+            query_type,
+            include_inactive: false,
+            levels: None,
+        }
+    }
+
+    /// Fetches the location with the given ID.
+    pub fn location(id: Id<resource::Location>) -> Self {
+        Self::new(id, LocationQueryType::Location)
+    }
+
+    /// Fetches a page of the store's active locations.
+    ///
+    /// Run it with [`build_connection`](Self::build_connection) or
+    /// [`stream`](Self::stream).
+    pub fn locations(conn: ProductsConnection) -> Self {
+        Self::new(Id::default(), LocationQueryType::Locations(conn))
+    }
+
+    /// Makes a [`locations`](Self::locations) query return deactivated
+    /// locations too.
+    pub fn include_inactive(mut self) -> Self {
+        self.include_inactive = true;
+        self
+    }
+
+    pub fn name(mut self) -> Self {
+        self.fields.insert("name".into(), PhantomData);
+        self
+    }
+
+    pub fn address(mut self) -> Self {
+        self.fields.insert(
+            "address { address1 address2 city province provinceCode country countryCode zip phone }"
+                .into(),
+            PhantomData,
+        );
+        self
+    }
+
+    pub fn is_active(mut self) -> Self {
+        self.fields.insert("isActive".into(), PhantomData);
+        self
+    }
+
+    pub fn fulfills_online_orders(mut self) -> Self {
+        self.fields
+            .insert("fulfillsOnlineOrders".into(), PhantomData);
+        self
+    }
+
+    /// Also fetches a page of the inventory levels of each location, with the
+    /// given quantities.
+    pub fn inventory_levels(
+        mut self,
+        conn: ProductsConnection,
+        quantities: &[InventoryQuantityName],
+    ) -> Self {
+        self.levels = Some((conn, quantities.to_vec()));
+        self
+    }
+
+    fn selection(&self) -> String {
+        let mut fields: Vec<String> = self.fields.keys().cloned().collect();
+
+        if let Some((conn, _)) = &self.levels {
+            fields.push(connection_query(
+                "inventoryLevels",
+                &conn.arguments(),
+                INVENTORY_LEVEL_FIELDS,
+            ));
+        }
+
+        fields.join("\n,")
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let fields = self.selection();
+        let (names_variable, names) = match &self.levels {
+            Some((_, names)) => (", $names: [String!]!", Some(json!(names))),
+            None => ("", None),
+        };
+
+        let request = match &self.query_type {
+            LocationQueryType::Location => GraphQlRequest::new(format!(
+                "query location($id: ID!{}) {{ location(id: $id) {{ {} }} }}",
+                names_variable, fields
+            ))
+            .with_variable("id", self.id.inner().clone()),
+
+            LocationQueryType::Locations(conn) => GraphQlRequest::new(format!(
+                "query locations({}, $includeInactive: Boolean{}) {{ {} }}",
+                CONNECTION_VARIABLES,
+                names_variable,
+                connection_query(
+                    "locations",
+                    &format!(
+                        "{}, includeInactive: $includeInactive",
+                        CONNECTION_ARGUMENTS
+                    ),
+                    &fields
+                )
+            ))
+            .with_variables(conn.variables())
+            .with_variable("includeInactive", self.include_inactive),
+        };
+
+        match names {
+            Some(names) => request.with_variable("names", names),
+            None => request,
+        }
+    }
+
+    /// Sends the query through `client` and returns the location.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<Location> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::Location(l) => Ok(l),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Sends a [`locations`](Self::locations) query through `client` and
+    /// returns the page of locations.
+    pub async fn build_connection(self, client: &ShopifyClient) -> ShopifyResult<Edges<Location>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::Locations(l) => Ok(l),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Runs a [`locations`](Self::locations) query page by page, yielding
+    /// every location until the connection is exhausted, or a single
+    /// [`ShopifyGqlError::InvalidQuery`] if the builder isn't a `locations`
+    /// query.
+    pub fn stream(self, client: &ShopifyClient) -> impl Stream<Item = ShopifyResult<Location>> {
+        let first = match &self.query_type {
+            LocationQueryType::Locations(conn) => Ok(conn.clone()),
+            _ => Err(ShopifyGqlError::InvalidQuery(
+                "`stream` requires a `LocationQueryBuilder::locations` query".into(),
+            )),
+        };
+
+        stream_connection(
+            self,
+            first,
+            client,
+            move |page, conn| page.query_type = LocationQueryType::Locations(conn),
+            |page, client| async move { page.build_connection(&client).await },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn passes_filters_as_variables() {
+        let request = LocationQueryBuilder::locations(ProductsConnection::First(10))
+            .include_inactive()
+            .name()
+            .inventory_levels(
+                ProductsConnection::First(50),
+                &[InventoryQuantityName::Available],
+            )
+            .request();
+
+        assert!(request.query().contains("inventoryLevels(first: 50)"));
+        assert!(request.query().contains("$names: [String!]!"));
+        assert!(request
+            .query()
+            .contains("includeInactive: $includeInactive"));
+        assert_eq!(request.variables()["first"], json!(10));
+        assert_eq!(request.variables()["includeInactive"], json!(true));
+        assert_eq!(request.variables()["names"], json!(["available"]));
+
+        let request = LocationQueryBuilder::location(Id::location("3").unwrap()).request();
+        assert!(!request.query().contains("$names"));
+        assert_eq!(
+            Value::Object(request.variables().clone()),
+            json!({ "id": "gid://shopify/Location/3" })
+        );
+    }
+
+    #[test]
+    fn parses_location_fields() {
+        let location: Location = serde_json::from_value(json!({
+            "id": "gid://shopify/Location/3",
+            "name": "Warehouse",
+            "address": {
+                "address1": "1 Main St",
+                "address2": null,
+                "city": "Ottawa",
+                "province": "Ontario",
+                "provinceCode": "ON",
+                "country": "Canada",
+                "countryCode": "CA",
+                "zip": "K1A 0A1",
+                "phone": null
+            },
+            "isActive": true,
+            "fulfillsOnlineOrders": false
+        }))
+        .unwrap();
+
+        assert_eq!(location.name(), Some(&"Warehouse".into()));
+        assert_eq!(
+            location.address().unwrap().province_code(),
+            Some(&"ON".into())
+        );
+        assert_eq!(location.is_active(), Some(true));
+        assert_eq!(location.fulfills_online_orders(), Some(false));
+        assert_eq!(location.inventory_levels(), None);
+    }
+}
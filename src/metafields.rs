@@ -0,0 +1,563 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use crate::{
+    common::{resource, AnyResource, CurrencyCode, Id, Money},
+    products::{bulk_connection_query, connection_query, quote, ProductsConnection},
+    utils::{
+        mutation_payload, run_request, GraphQlRequest, ResponseTypes, ShopifyClient,
+        ShopifyGqlError, ShopifyResult,
+    },
+};
+
+/// The fields selected whenever a metafield is fetched.
+pub(crate) const METAFIELD_FIELDS: &str = "id namespace key type value createdAt updatedAt";
+
+/// The prefix of the aliases that [`metafield_selection`] gives to metafields
+/// selected by namespace and key.
+pub(crate) const METAFIELD_ALIAS_PREFIX: &str = "metafield_";
+
+/// Renders a `metafield(namespace:, key:)` field.
+///
+/// Each one gets its own alias, so that several can be selected on the same
+/// object. The namespace and key are hex-encoded in the alias, as they may
+/// contain characters that aliases can't, and no two of them may share one.
+pub(crate) fn metafield_selection(namespace: &str, key: &str) -> String {
+    let hex = |s: &str| -> String { s.bytes().map(|b| format!("{:02x}", b)).collect() };
+    let alias = format!("{}_{}", hex(namespace), hex(key));
+
+    format!(
+        "{}{}: metafield(namespace: {}, key: {}) {{ {} }}",
+        METAFIELD_ALIAS_PREFIX,
+        alias,
+        quote(namespace),
+        quote(key),
+        METAFIELD_FIELDS
+    )
+}
+
+/// Collects the metafields aliased by [`metafield_selection`] from the fields
+/// of a flattened object, skipping every other key such as `__typename`.
+pub(crate) fn deserialize_aliased_metafields<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Option<Metafield>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Map::<String, Value>::deserialize(deserializer)?
+        .into_iter()
+        .filter(|(alias, _)| alias.starts_with(METAFIELD_ALIAS_PREFIX))
+        .map(|(alias, value)| {
+            let metafield = serde_json::from_value(value).map_err(de::Error::custom)?;
+            Ok((alias, metafield))
+        })
+        .collect()
+}
+
+/// Renders a paginated `metafields` connection, optionally limited to one
+/// namespace.
+///
+/// Without `conn`, the connection is rendered for a bulk operation, which
+/// walks every page.
+pub(crate) fn metafields_connection(
+    conn: Option<&ProductsConnection>,
+    namespace: Option<&str>,
+) -> String {
+    let mut args: Vec<String> = conn
+        .map(ProductsConnection::arguments)
+        .into_iter()
+        .collect();
+    if let Some(namespace) = namespace {
+        args.push(format!("namespace: {}", quote(namespace)));
+    }
+
+    match conn {
+        Some(_) => connection_query("metafields", &args.join(", "), METAFIELD_FIELDS),
+        None => bulk_connection_query("metafields", &args.join(", "), METAFIELD_FIELDS),
+    }
+}
+
+/// A piece of custom data attached to a resource, identified by its namespace
+/// and key.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Metafield {
+    id: Id<resource::Metafield>,
+    namespace: String,
+    key: String,
+
+    #[serde(rename = "type")]
+    type_name: String,
+
+    value: String,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl Metafield {
+    pub fn id(&self) -> &Id<resource::Metafield> {
+        &self.id
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The type of the value, such as `single_line_text_field` or
+    /// `list.product_reference`.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// The value as Shopify stores it, which is JSON for lists and most
+    /// structured types.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Parses the value according to the metafield's type.
+    pub fn typed_value(&self) -> ShopifyResult<MetafieldValue> {
+        MetafieldValue::parse(&self.type_name, &self.value)
+    }
+
+    pub fn created_at(&self) -> Option<&DateTime<Utc>> {
+        self.created_at.as_ref()
+    }
+
+    pub fn updated_at(&self) -> Option<&DateTime<Utc>> {
+        self.updated_at.as_ref()
+    }
+}
+
+/// The value of a metafield, parsed according to its type.
+#[derive(Debug, PartialEq, Clone)]
+pub enum MetafieldValue {
+    Boolean(bool),
+
+    /// A hex color code, such as `#fff123`.
+    Color(String),
+
+    Date(NaiveDate),
+    DateTime(DateTime<Utc>),
+    Json(Value),
+    Money(Money),
+    MultiLineText(String),
+    NumberDecimal(Decimal),
+    NumberInteger(i64),
+
+    /// The ID of another object, for any of the `_reference` types, such as
+    /// `product_reference`.
+    Reference {
+        type_name: String,
+        id: Id,
+    },
+
+    SingleLineText(String),
+    Url(String),
+
+    /// One of the `list.` types, whose items all have `item_type`.
+    List {
+        item_type: String,
+        items: Vec<MetafieldValue>,
+    },
+
+    /// A type that isn't parsed, such as `rating` or `dimension`, with its raw
+    /// value.
+    Other {
+        type_name: String,
+        value: String,
+    },
+}
+
+impl MetafieldValue {
+    /// Parses a value stored as `value` in a metafield of type `type_name`.
+    pub fn parse(type_name: &str, value: &str) -> ShopifyResult<Self> {
+        let invalid =
+            || ShopifyGqlError::InvalidMetafieldValue(format!("{} as {}", value, type_name));
+
+        if let Some(item_type) = type_name.strip_prefix("list.") {
+            let items: Vec<Value> = serde_json::from_str(value).map_err(|_| invalid())?;
+            let items = items
+                .into_iter()
+                .map(|item| match item {
+                    Value::String(s) => Self::parse(item_type, &s),
+                    other => Self::parse(item_type, &other.to_string()),
+                })
+                .collect::<ShopifyResult<_>>()?;
+
+            return Ok(MetafieldValue::List {
+                item_type: item_type.into(),
+                items,
+            });
+        }
+
+        Ok(match type_name {
+            "boolean" => MetafieldValue::Boolean(value.parse().map_err(|_| invalid())?),
+            "color" => MetafieldValue::Color(value.into()),
+            "date" => MetafieldValue::Date(value.parse().map_err(|_| invalid())?),
+
+            // Shopify leaves out the offset of values that were set without
+            // one, which are in UTC.
+            "date_time" => MetafieldValue::DateTime(match value.parse::<DateTime<Utc>>() {
+                Ok(date_time) => date_time,
+                Err(_) => value
+                    .parse::<NaiveDateTime>()
+                    .map_err(|_| invalid())?
+                    .and_utc(),
+            }),
+
+            "json" => MetafieldValue::Json(serde_json::from_str(value).map_err(|_| invalid())?),
+
+            "money" => {
+                let money: MoneyValue = serde_json::from_str(value).map_err(|_| invalid())?;
+                let amount = Decimal::from_str_exact(&money.amount).map_err(|_| invalid())?;
+
+                MetafieldValue::Money(Money::new(amount).with_currency(money.currency_code))
+            }
+
+            "multi_line_text_field" => MetafieldValue::MultiLineText(value.into()),
+            "number_decimal" => MetafieldValue::NumberDecimal(
+                Decimal::from_str_exact(value).map_err(|_| invalid())?,
+            ),
+            "number_integer" => {
+                MetafieldValue::NumberInteger(value.parse().map_err(|_| invalid())?)
+            }
+            "single_line_text_field" => MetafieldValue::SingleLineText(value.into()),
+            "url" => MetafieldValue::Url(value.into()),
+
+            t if t.ends_with("_reference") => MetafieldValue::Reference {
+                type_name: t.into(),
+                id: Id::<AnyResource>::parse(value)?,
+            },
+
+            t => MetafieldValue::Other {
+                type_name: t.into(),
+                value: value.into(),
+            },
+        })
+    }
+
+    /// The metafield type of the value, such as `number_integer`.
+    pub fn type_name(&self) -> String {
+        match self {
+            MetafieldValue::Boolean(_) => "boolean".into(),
+            MetafieldValue::Color(_) => "color".into(),
+            MetafieldValue::Date(_) => "date".into(),
+            MetafieldValue::DateTime(_) => "date_time".into(),
+            MetafieldValue::Json(_) => "json".into(),
+            MetafieldValue::Money(_) => "money".into(),
+            MetafieldValue::MultiLineText(_) => "multi_line_text_field".into(),
+            MetafieldValue::NumberDecimal(_) => "number_decimal".into(),
+            MetafieldValue::NumberInteger(_) => "number_integer".into(),
+            MetafieldValue::Reference { type_name, .. } => type_name.clone(),
+            MetafieldValue::SingleLineText(_) => "single_line_text_field".into(),
+            MetafieldValue::Url(_) => "url".into(),
+            MetafieldValue::List { item_type, .. } => format!("list.{}", item_type),
+            MetafieldValue::Other { type_name, .. } => type_name.clone(),
+        }
+    }
+
+    /// The value as Shopify stores it, ready to be sent in a mutation.
+    pub fn to_value_string(&self) -> String {
+        match self.to_json() {
+            Value::String(s) => s,
+            other => other.to_string(),
+        }
+    }
+
+    /// The value as an item of a list value.
+    fn to_json(&self) -> Value {
+        match self {
+            MetafieldValue::Boolean(b) => b.to_string().into(),
+            MetafieldValue::Color(s)
+            | MetafieldValue::MultiLineText(s)
+            | MetafieldValue::SingleLineText(s)
+            | MetafieldValue::Url(s) => s.clone().into(),
+            MetafieldValue::Date(date) => date.format("%Y-%m-%d").to_string().into(),
+            MetafieldValue::DateTime(date_time) => date_time.to_rfc3339().into(),
+            MetafieldValue::Json(value) => value.clone(),
+            MetafieldValue::Money(money) => json!({
+                "amount": money.amount().to_string(),
+                "currency_code": money.currency_code(),
+            }),
+            MetafieldValue::NumberDecimal(n) => n.to_string().into(),
+            MetafieldValue::NumberInteger(n) => (*n).into(),
+            MetafieldValue::Reference { id, .. } => id.inner().clone().into(),
+            MetafieldValue::List { items, .. } => {
+                items.iter().map(MetafieldValue::to_json).collect()
+            }
+            MetafieldValue::Other { value, .. } => value.clone().into(),
+        }
+    }
+}
+
+/// The JSON of a `money` metafield value.
+#[derive(Debug, Deserialize)]
+struct MoneyValue {
+    amount: String,
+    currency_code: CurrencyCode,
+}
+
+/// A metafield to create or update, for [`MetafieldsSet`] or the
+/// `update_metafields` methods of the product and variant builders.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MetafieldInput {
+    namespace: String,
+    key: String,
+
+    #[serde(rename = "type")]
+    type_name: String,
+
+    value: String,
+}
+
+impl MetafieldInput {
+    pub fn new(namespace: &str, key: &str, value: &MetafieldValue) -> Self {
+        Self {
+            namespace: namespace.into(),
+            key: key.into(),
+            type_name: value.type_name(),
+            value: value.to_value_string(),
+        }
+    }
+}
+
+/// Identifies a metafield by the object it belongs to, its namespace and its
+/// key.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MetafieldIdentifier {
+    owner_id: Id,
+    namespace: String,
+    key: String,
+}
+
+impl MetafieldIdentifier {
+    pub fn new<R>(owner_id: &Id<R>, namespace: &str, key: &str) -> Self {
+        Self {
+            owner_id: owner_id.clone().into_any(),
+            namespace: namespace.into(),
+            key: key.into(),
+        }
+    }
+
+    pub fn owner_id(&self) -> &Id {
+        &self.owner_id
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+/// Creates or updates metafields on any objects, with `metafieldsSet`.
+///
+/// The metafields are set atomically: if one of them is invalid, none are
+/// changed.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MetafieldsSet {
+    metafields: Vec<Value>,
+}
+
+impl MetafieldsSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a metafield on the object with the given ID.
+    pub fn set<R>(mut self, owner_id: &Id<R>, input: MetafieldInput) -> Self {
+        let mut metafield = json!(input);
+        metafield["ownerId"] = owner_id.inner().clone().into();
+
+        self.metafields.push(metafield);
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        GraphQlRequest::new(format!(
+            "mutation metafieldsSet($metafields: [MetafieldsSetInput!]!) {{ metafieldsSet(metafields: $metafields) {{ metafields {{ {} }} userErrors {{ field message code }} }} }}",
+            METAFIELD_FIELDS
+        ))
+        .with_variable("metafields", self.metafields.clone())
+    }
+
+    /// Sends the mutation through `client` and returns the metafields that
+    /// were set.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<Vec<Metafield>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::MetafieldsSet {
+                metafields,
+                user_errors,
+            } => mutation_payload(metafields, user_errors),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+}
+
+/// Deletes metafields from any objects, with `metafieldsDelete`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MetafieldsDelete {
+    metafields: Vec<MetafieldIdentifier>,
+}
+
+impl MetafieldsDelete {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deletes the metafield with the given namespace and key from the object
+    /// with the given ID.
+    pub fn delete<R>(mut self, owner_id: &Id<R>, namespace: &str, key: &str) -> Self {
+        self.metafields
+            .push(MetafieldIdentifier::new(owner_id, namespace, key));
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        GraphQlRequest::new(
+            "mutation metafieldsDelete($metafields: [MetafieldIdentifierInput!]!) { metafieldsDelete(metafields: $metafields) { deletedMetafields { ownerId namespace key } userErrors { field message } } }",
+        )
+        .with_variable("metafields", json!(self.metafields))
+    }
+
+    /// Sends the mutation through `client` and returns the metafields that
+    /// were deleted, leaving out the ones that didn't exist.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<Vec<MetafieldIdentifier>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::MetafieldsDelete {
+                deleted_metafields,
+                user_errors,
+            } => Ok(mutation_payload(deleted_metafields, user_errors)?
+                .into_iter()
+                .flatten()
+                .collect()),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_typed_values() {
+        assert_eq!(
+            MetafieldValue::parse("number_integer", "42").unwrap(),
+            MetafieldValue::NumberInteger(42)
+        );
+        assert_eq!(
+            MetafieldValue::parse("money", r#"{"amount":"5.99","currency_code":"CAD"}"#).unwrap(),
+            MetafieldValue::Money(
+                Money::new(Decimal::new(599, 2)).with_currency(CurrencyCode::CAD)
+            )
+        );
+        assert_eq!(
+            MetafieldValue::parse("date_time", "2024-01-02T03:04:05").unwrap(),
+            MetafieldValue::DateTime("2024-01-02T03:04:05Z".parse().unwrap())
+        );
+
+        let refs = MetafieldValue::parse(
+            "list.product_reference",
+            r#"["gid://shopify/Product/1","gid://shopify/Product/2"]"#,
+        )
+        .unwrap();
+        match &refs {
+            MetafieldValue::List { item_type, items } => {
+                assert_eq!(item_type, "product_reference");
+                assert_eq!(
+                    items[1],
+                    MetafieldValue::Reference {
+                        type_name: "product_reference".into(),
+                        id: Id::product("2").unwrap().into_any(),
+                    }
+                );
+            }
+            v => panic!("unexpected value: {:?}", v),
+        }
+
+        assert!(MetafieldValue::parse("number_integer", "4.2").is_err());
+    }
+
+    #[test]
+    fn round_trips_values_through_inputs() {
+        let values = [
+            MetafieldValue::parse("list.single_line_text_field", r#"["a","b"]"#).unwrap(),
+            MetafieldValue::parse("list.number_integer", "[1,2]").unwrap(),
+            MetafieldValue::parse("json", r#"{"care":["wash cold"]}"#).unwrap(),
+            MetafieldValue::parse("boolean", "true").unwrap(),
+            MetafieldValue::parse("rating", r#"{"value":"4","scale_min":"1","scale_max":"5"}"#)
+                .unwrap(),
+        ];
+
+        for value in values {
+            let input = MetafieldInput::new("custom", "key", &value);
+            let json = json!(input);
+
+            assert_eq!(json["type"], json!(value.type_name()));
+            assert_eq!(
+                MetafieldValue::parse(
+                    json["type"].as_str().unwrap(),
+                    json["value"].as_str().unwrap()
+                )
+                .unwrap(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn aliases_each_metafield() {
+        assert_eq!(
+            metafield_selection("custom", "care-guide"),
+            format!(
+                "metafield_637573746f6d_636172652d6775696465: metafield(namespace: \"custom\", key: \"care-guide\") {{ {} }}",
+                METAFIELD_FIELDS
+            )
+        );
+
+        let alias = |namespace, key| {
+            let selection = metafield_selection(namespace, key);
+            selection.split(':').next().unwrap().to_owned()
+        };
+        assert_ne!(alias("custom", "care-guide"), alias("custom", "care_guide"));
+        assert_ne!(alias("a_b", "c"), alias("a", "b_c"));
+    }
+
+    #[test]
+    fn sets_metafields_on_owners() {
+        let request = MetafieldsSet::new()
+            .set(
+                &Id::product("1").unwrap(),
+                MetafieldInput::new("erp", "code", &MetafieldValue::SingleLineText("A-1".into())),
+            )
+            .request();
+
+        assert_eq!(
+            request.variables()["metafields"],
+            json!([{
+                "ownerId": "gid://shopify/Product/1",
+                "namespace": "erp",
+                "key": "code",
+                "type": "single_line_text_field",
+                "value": "A-1"
+            }])
+        );
+    }
+}
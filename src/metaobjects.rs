@@ -0,0 +1,645 @@
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    common::{resource, stream_connection, Edges, Id},
+    products::{connection_query, ProductsConnection, CONNECTION_ARGUMENTS, CONNECTION_VARIABLES},
+    utils::{
+        mutation_payload, run_request, GraphQlRequest, ResponseTypes, ShopifyClient,
+        ShopifyGqlError, ShopifyResult,
+    },
+};
+
+/// The fields selected whenever a metaobject is fetched.
+const METAOBJECT_FIELDS: &str = "id handle type displayName updatedAt capabilities { publishable { status } } fields { key type value }";
+
+/// The field types whose values are plain strings rather than JSON.
+const TEXT_FIELD_TYPES: &[&str] = &[
+    "color",
+    "date",
+    "date_time",
+    "id",
+    "multi_line_text_field",
+    "single_line_text_field",
+    "url",
+];
+
+/// Maps a Rust struct to the `fields { key value }` of a metaobject, through
+/// its `Serialize` and `Deserialize` implementations.
+///
+/// Each struct field is a metaobject field with the same key, so rename them
+/// with `#[serde(rename = "...")]` where the keys differ. Text, date, color,
+/// URL and reference fields map to strings, and the other types to their JSON
+/// value, such as a number for `number_integer`, a `bool` for `boolean` or a
+/// `Vec` for the `list.` types.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use shopify_gql::MetaobjectFields;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct SizeChart {
+///     title: String,
+///     sizes: Vec<String>,
+///     chest_cm: Option<i64>,
+/// }
+///
+/// impl MetaobjectFields for SizeChart {
+///     const TYPE: &'static str = "size_chart";
+/// }
+/// ```
+pub trait MetaobjectFields: Serialize + DeserializeOwned {
+    /// The type of the metaobjects, such as `size_chart`.
+    const TYPE: &'static str;
+
+    /// Returns the metaobject fields of `self`.
+    ///
+    /// Fields that serialize to `null` are left out, so an update leaves their
+    /// values unchanged.
+    fn to_fields(&self) -> ShopifyResult<Vec<MetaobjectFieldInput>> {
+        let invalid = |e: String| ShopifyGqlError::InvalidMetaobjectFields(e);
+
+        let fields = match serde_json::to_value(self).map_err(|e| invalid(e.to_string()))? {
+            Value::Object(fields) => fields,
+            other => return Err(invalid(format!("{} isn't a struct", other))),
+        };
+
+        Ok(fields
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    Value::Null => return None,
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+
+                Some(MetaobjectFieldInput { key, value })
+            })
+            .collect())
+    }
+
+    /// Reads `Self` from the fields of a metaobject.
+    fn from_fields(fields: &[MetaobjectField]) -> ShopifyResult<Self> {
+        let object: Map<String, Value> = fields
+            .iter()
+            .map(|field| (field.key.clone(), field.json_value()))
+            .collect();
+
+        serde_json::from_value(object.into())
+            .map_err(|e| ShopifyGqlError::InvalidMetaobjectFields(e.to_string()))
+    }
+}
+
+/// Whether a metaobject is visible on the storefront, for the types with the
+/// `publishable` capability.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum MetaobjectStatus {
+    ACTIVE,
+    DRAFT,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+struct MetaobjectCapabilities {
+    publishable: Option<MetaobjectPublishable>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+struct MetaobjectPublishable {
+    status: MetaobjectStatus,
+}
+
+/// An entry of a custom object type, such as a size chart or a brand page,
+/// with the fields declared by its
+/// [`MetaobjectDefinition`](crate::definitions::MetaobjectDefinition).
+///
+/// Every field is fetched, so they are only `None` on the result of a
+/// [`metaobject_delete`](MetaobjectQueryBuilder::metaobject_delete).
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Metaobject {
+    id: Id<resource::Metaobject>,
+    handle: Option<String>,
+
+    #[serde(rename = "type")]
+    type_name: Option<String>,
+
+    display_name: Option<String>,
+    updated_at: Option<DateTime<Utc>>,
+    capabilities: Option<MetaobjectCapabilities>,
+    fields: Option<Vec<MetaobjectField>>,
+}
+
+impl Metaobject {
+    pub fn id(&self) -> &Id<resource::Metaobject> {
+        &self.id
+    }
+
+    /// The handle of the metaobject, unique among the metaobjects of its
+    /// type.
+    pub fn handle(&self) -> Option<&String> {
+        self.handle.as_ref()
+    }
+
+    /// The type of the metaobject, such as `size_chart`.
+    pub fn type_name(&self) -> Option<&String> {
+        self.type_name.as_ref()
+    }
+
+    pub fn display_name(&self) -> Option<&String> {
+        self.display_name.as_ref()
+    }
+
+    pub fn updated_at(&self) -> Option<&DateTime<Utc>> {
+        self.updated_at.as_ref()
+    }
+
+    /// The storefront status, or `None` if the type isn't publishable.
+    pub fn status(&self) -> Option<MetaobjectStatus> {
+        Some(self.capabilities.as_ref()?.publishable.as_ref()?.status)
+    }
+
+    pub fn fields(&self) -> Option<&[MetaobjectField]> {
+        self.fields.as_deref()
+    }
+
+    /// Returns the field with the given key.
+    pub fn field(&self, key: &str) -> Option<&MetaobjectField> {
+        self.fields.as_ref()?.iter().find(|f| f.key == key)
+    }
+
+    /// Reads the fields into a [`MetaobjectFields`] struct.
+    pub fn fields_as<T: MetaobjectFields>(&self) -> ShopifyResult<T> {
+        T::from_fields(self.fields.as_deref().unwrap_or_default())
+    }
+}
+
+/// A field of a [`Metaobject`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct MetaobjectField {
+    key: String,
+
+    #[serde(rename = "type")]
+    type_name: String,
+
+    /// `None` if the field is empty.
+    value: Option<String>,
+}
+
+impl MetaobjectField {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The type of the field, such as `single_line_text_field`.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    pub fn value(&self) -> Option<&String> {
+        self.value.as_ref()
+    }
+
+    /// The value as [`MetaobjectFields::from_fields`] reads it.
+    fn json_value(&self) -> Value {
+        let Some(value) = &self.value else {
+            return Value::Null;
+        };
+
+        // Lists are stored as JSON arrays, whatever the type of their items.
+        if self.type_name.starts_with("list.") {
+            return serde_json::from_str(value).unwrap_or_else(|_| value.clone().into());
+        }
+
+        if TEXT_FIELD_TYPES.contains(&self.type_name.as_str())
+            || self.type_name.ends_with("_reference")
+        {
+            return value.clone().into();
+        }
+
+        serde_json::from_str(value).unwrap_or_else(|_| value.clone().into())
+    }
+}
+
+/// The value of a field of a [`MetaobjectInput`].
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct MetaobjectFieldInput {
+    key: String,
+    value: String,
+}
+
+impl MetaobjectFieldInput {
+    /// Sets the field called `key` to `value`, as Shopify stores it.
+    pub fn new(key: &str, value: &str) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// The handle, fields and status to give a metaobject.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MetaobjectInput {
+    type_name: String,
+    handle: Option<String>,
+    fields: Vec<MetaobjectFieldInput>,
+    status: Option<MetaobjectStatus>,
+}
+
+impl MetaobjectInput {
+    /// A metaobject of type `type_name`, such as `size_chart`.
+    pub fn new(type_name: &str) -> Self {
+        Self {
+            type_name: type_name.into(),
+            handle: None,
+            fields: vec![],
+            status: None,
+        }
+    }
+
+    /// A metaobject of type `T::TYPE` with the fields of `fields`.
+    pub fn from_fields<T: MetaobjectFields>(fields: &T) -> ShopifyResult<Self> {
+        Ok(Self {
+            fields: fields.to_fields()?,
+            ..Self::new(T::TYPE)
+        })
+    }
+
+    pub fn handle(mut self, handle: &str) -> Self {
+        self.handle = Some(handle.into());
+        self
+    }
+
+    pub fn field(mut self, field: MetaobjectFieldInput) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Sets the storefront status, for the types with the `publishable`
+    /// capability.
+    pub fn status(mut self, status: MetaobjectStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// The `MetaobjectCreateInput`, or with `with_type` unset, the
+    /// `MetaobjectUpdateInput` or `MetaobjectUpsertInput`.
+    fn input(&self, with_type: bool) -> Value {
+        let mut input = Map::new();
+        input.insert("fields".into(), json!(self.fields));
+
+        if with_type {
+            input.insert("type".into(), self.type_name.clone().into());
+        }
+        if let Some(handle) = &self.handle {
+            input.insert("handle".into(), handle.clone().into());
+        }
+        if let Some(status) = self.status {
+            input.insert(
+                "capabilities".into(),
+                json!({ "publishable": { "status": status } }),
+            );
+        }
+
+        input.into()
+    }
+}
+
+/// All possible queries and mutations on a `Metaobject`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum MetaobjectQueryType {
+    Metaobject(Id<resource::Metaobject>),
+    MetaobjectByHandle,
+    Metaobjects(String, ProductsConnection),
+    MetaobjectCreate,
+    MetaobjectUpdate,
+    MetaobjectUpsert,
+    MetaobjectDelete,
+}
+
+/// Builds and runs a query or mutation that returns a [`Metaobject`].
+///
+/// Every field of the metaobjects is always fetched.
+#[derive(Debug, Clone)]
+pub struct MetaobjectQueryBuilder {
+    query_type: MetaobjectQueryType,
+
+    /// The variables of a mutation or a `metaobjectByHandle` query, or the
+    /// filters of a `metaobjects` query.
+    inputs: Map<String, Value>,
+}
+
+impl MetaobjectQueryBuilder {
+    fn new(query_type: MetaobjectQueryType, inputs: Map<String, Value>) -> Self {
+        Self { query_type, inputs }
+    }
+
+    /// Fetches the metaobject with the given ID.
+    pub fn metaobject(id: Id<resource::Metaobject>) -> Self {
+        Self::new(MetaobjectQueryType::Metaobject(id), Map::new())
+    }
+
+    /// Fetches the metaobject of type `type_name` with the given handle.
+    pub fn metaobject_by_handle(type_name: &str, handle: &str) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert(
+            "handle".into(),
+            json!({ "type": type_name, "handle": handle }),
+        );
+
+        Self::new(MetaobjectQueryType::MetaobjectByHandle, inputs)
+    }
+
+    /// Fetches a page of the metaobjects of type `type_name`.
+    ///
+    /// Narrow the results down with [`search`](Self::search), and run it with
+    /// [`build_connection`](Self::build_connection) or
+    /// [`stream`](Self::stream).
+    pub fn metaobjects(type_name: &str, conn: ProductsConnection) -> Self {
+        Self::new(
+            MetaobjectQueryType::Metaobjects(type_name.into(), conn),
+            Map::new(),
+        )
+    }
+
+    /// Only returns the metaobjects matching a search query, such as
+    /// `display_name:Classic`.
+    pub fn search(mut self, query: &str) -> Self {
+        self.inputs.insert("query".into(), query.into());
+        self
+    }
+
+    /// Orders the metaobjects by `sort_key`, such as `display_name` or
+    /// `updated_at`.
+    pub fn sort_key(mut self, sort_key: &str) -> Self {
+        self.inputs.insert("sortKey".into(), sort_key.into());
+        self
+    }
+
+    pub fn reverse(mut self) -> Self {
+        self.inputs.insert("reverse".into(), true.into());
+        self
+    }
+
+    /// Creates a metaobject and returns it.
+    pub fn metaobject_create(metaobject: &MetaobjectInput) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("metaobject".into(), metaobject.input(true));
+
+        Self::new(MetaobjectQueryType::MetaobjectCreate, inputs)
+    }
+
+    /// Updates the metaobject with the given ID and returns it.
+    ///
+    /// Fields that aren't in `metaobject` are left unchanged, and its type is
+    /// ignored.
+    pub fn metaobject_update(id: Id<resource::Metaobject>, metaobject: &MetaobjectInput) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("id".into(), json!(id));
+        inputs.insert("metaobject".into(), metaobject.input(false));
+
+        Self::new(MetaobjectQueryType::MetaobjectUpdate, inputs)
+    }
+
+    /// Updates the metaobject with the type of `metaobject` and the given
+    /// handle, or creates it if there isn't one, and returns it.
+    pub fn metaobject_upsert(handle: &str, metaobject: &MetaobjectInput) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert(
+            "handle".into(),
+            json!({ "type": metaobject.type_name, "handle": handle }),
+        );
+        inputs.insert("metaobject".into(), metaobject.input(false));
+
+        Self::new(MetaobjectQueryType::MetaobjectUpsert, inputs)
+    }
+
+    /// Deletes the metaobject with the given ID.
+    ///
+    /// Only the `id` of the returned metaobject is set.
+    pub fn metaobject_delete(id: Id<resource::Metaobject>) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("id".into(), json!(id));
+
+        Self::new(MetaobjectQueryType::MetaobjectDelete, inputs)
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let fields = METAOBJECT_FIELDS;
+
+        match &self.query_type {
+            MetaobjectQueryType::Metaobject(id) => GraphQlRequest::new(format!(
+                "query metaobject($id: ID!) {{ metaobject(id: $id) {{ {} }} }}",
+                fields
+            ))
+            .with_variable("id", id.inner().clone()),
+
+            MetaobjectQueryType::MetaobjectByHandle => GraphQlRequest::new(format!(
+                "query metaobjectByHandle($handle: MetaobjectHandleInput!) {{ metaobjectByHandle(handle: $handle) {{ {} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+
+            MetaobjectQueryType::Metaobjects(type_name, conn) => GraphQlRequest::new(format!(
+                "query metaobjects({}, $type: String!, $query: String, $sortKey: String, $reverse: Boolean) {{ {} }}",
+                CONNECTION_VARIABLES,
+                connection_query(
+                    "metaobjects",
+                    &format!(
+                        "{}, type: $type, query: $query, sortKey: $sortKey, reverse: $reverse",
+                        CONNECTION_ARGUMENTS
+                    ),
+                    fields
+                )
+            ))
+            .with_variables(conn.variables())
+            .with_variable("type", type_name.clone())
+            .with_variables(self.inputs.clone()),
+
+            MetaobjectQueryType::MetaobjectCreate => GraphQlRequest::new(format!(
+                "mutation metaobjectCreate($metaobject: MetaobjectCreateInput!) {{ metaobjectCreate(metaobject: $metaobject) {{ metaobject {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+
+            MetaobjectQueryType::MetaobjectUpdate => GraphQlRequest::new(format!(
+                "mutation metaobjectUpdate($id: ID!, $metaobject: MetaobjectUpdateInput!) {{ metaobjectUpdate(id: $id, metaobject: $metaobject) {{ metaobject {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+
+            MetaobjectQueryType::MetaobjectUpsert => GraphQlRequest::new(format!(
+                "mutation metaobjectUpsert($handle: MetaobjectHandleInput!, $metaobject: MetaobjectUpsertInput!) {{ metaobjectUpsert(handle: $handle, metaobject: $metaobject) {{ metaobject {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs.clone()),
+
+            MetaobjectQueryType::MetaobjectDelete => GraphQlRequest::new(
+                "mutation metaobjectDelete($id: ID!) { metaobjectDelete(id: $id) { deletedId userErrors { field message code } } }",
+            )
+            .with_variables(self.inputs.clone()),
+        }
+    }
+
+    /// Sends the query through `client` and returns the metaobject.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<Metaobject> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::Metaobject(m) => Ok(m),
+
+            ResponseTypes::MetaobjectByHandle(m) => m.ok_or_else(|| {
+                ShopifyGqlError::ResponseError("No metaobject has the given handle".into())
+            }),
+
+            ResponseTypes::MetaobjectCreate {
+                metaobject,
+                user_errors,
+            }
+            | ResponseTypes::MetaobjectUpdate {
+                metaobject,
+                user_errors,
+            }
+            | ResponseTypes::MetaobjectUpsert {
+                metaobject,
+                user_errors,
+            } => mutation_payload(metaobject, user_errors),
+
+            ResponseTypes::MetaobjectDelete {
+                deleted_id,
+                user_errors,
+            } => mutation_payload(deleted_id, user_errors).map(|id| Metaobject {
+                id,
+                ..Metaobject::default()
+            }),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Sends a [`metaobjects`](Self::metaobjects) query through `client` and
+    /// returns the page of metaobjects.
+    pub async fn build_connection(
+        self,
+        client: &ShopifyClient,
+    ) -> ShopifyResult<Edges<Metaobject>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::Metaobjects(m) => Ok(m),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Runs a [`metaobjects`](Self::metaobjects) query page by page, yielding
+    /// every matching metaobject until the connection is exhausted.
+    ///
+    /// Yields a single [`ShopifyGqlError::InvalidQuery`] if the builder was
+    /// not created with [`metaobjects`](Self::metaobjects).
+    pub fn stream(self, client: &ShopifyClient) -> impl Stream<Item = ShopifyResult<Metaobject>> {
+        let first = match &self.query_type {
+            MetaobjectQueryType::Metaobjects(_, conn) => Ok(conn.clone()),
+            _ => Err(ShopifyGqlError::InvalidQuery(
+                "`stream` requires a `MetaobjectQueryBuilder::metaobjects` query".into(),
+            )),
+        };
+
+        stream_connection(
+            self,
+            first,
+            client,
+            |page, conn| {
+                if let MetaobjectQueryType::Metaobjects(_, page_conn) = &mut page.query_type {
+                    *page_conn = conn;
+                }
+            },
+            |page, client| async move { page.build_connection(&client).await },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct SizeChart {
+        title: String,
+        sizes: Vec<String>,
+        chest_cm: Option<i64>,
+        in_stock: bool,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        products: Vec<Id<resource::Product>>,
+    }
+
+    impl MetaobjectFields for SizeChart {
+        const TYPE: &'static str = "size_chart";
+    }
+
+    #[test]
+    fn upserts_structs_as_fields() {
+        let chart = SizeChart {
+            title: "Shirts".into(),
+            sizes: vec!["S".into(), "M".into()],
+            chest_cm: None,
+            in_stock: true,
+            products: vec![],
+        };
+        let input = MetaobjectInput::from_fields(&chart)
+            .unwrap()
+            .status(MetaobjectStatus::ACTIVE);
+
+        let request = MetaobjectQueryBuilder::metaobject_upsert("shirts", &input).request();
+
+        assert_eq!(
+            request.variables()["handle"],
+            json!({ "type": "size_chart", "handle": "shirts" })
+        );
+        assert_eq!(
+            request.variables()["metaobject"],
+            json!({
+                "fields": [
+                    { "key": "in_stock", "value": "true" },
+                    { "key": "sizes", "value": "[\"S\",\"M\"]" },
+                    { "key": "title", "value": "Shirts" }
+                ],
+                "capabilities": { "publishable": { "status": "ACTIVE" } }
+            })
+        );
+    }
+
+    #[test]
+    fn reads_fields_into_structs() {
+        let metaobject: Metaobject = serde_json::from_value(json!({
+            "id": "gid://shopify/Metaobject/1",
+            "handle": "shirts",
+            "type": "size_chart",
+            "displayName": "Shirts",
+            "updatedAt": "2024-05-01T10:00:00Z",
+            "capabilities": { "publishable": null },
+            "fields": [
+                { "key": "title", "type": "single_line_text_field", "value": "123" },
+                { "key": "sizes", "type": "list.single_line_text_field", "value": "[\"S\",\"M\"]" },
+                { "key": "chest_cm", "type": "number_integer", "value": "96" },
+                { "key": "in_stock", "type": "boolean", "value": "false" },
+                { "key": "products", "type": "list.product_reference", "value": "[\"gid://shopify/Product/1\",\"gid://shopify/Product/2\"]" }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(metaobject.status(), None);
+        assert_eq!(
+            metaobject.fields_as::<SizeChart>().unwrap(),
+            SizeChart {
+                title: "123".into(),
+                sizes: vec!["S".into(), "M".into()],
+                chest_cm: Some(96),
+                in_stock: false,
+                products: vec![Id::product("1").unwrap(), Id::product("2").unwrap()],
+            }
+        );
+    }
+}
@@ -0,0 +1,657 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    common::{resource, Id},
+    staged_uploads::{stage_upload, StagedUploadInput, StagedUploadTargetGenerateUploadResource},
+    utils::{
+        mutation_payload, run_request, GraphQlRequest, ResponseTypes, ShopifyClient,
+        ShopifyGqlError, ShopifyResult,
+    },
+};
+
+/// The fields selected whenever an `image` is fetched.
+pub(crate) const IMAGE_FIELDS: &str = "id url altText width height";
+
+/// The fields selected whenever a `media` is fetched, including those of each
+/// kind of media.
+pub(crate) const MEDIA_FIELDS: &str = "id alt mediaContentType status preview { status image { id url altText width height } } ... on MediaImage { image { id url altText width height } } ... on Video { sources { url mimeType format width height } } ... on ExternalVideo { embedUrl host } ... on Model3d { sources { url mimeType format filesize } }";
+
+/// The kind of a media file.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum MediaContentType {
+    /// A YouTube or Vimeo video.
+    EXTERNAL_VIDEO,
+    IMAGE,
+    MODEL_3D,
+
+    /// A video hosted by Shopify.
+    VIDEO,
+}
+
+/// A media file to attach to a product.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMediaInput {
+    original_source: String,
+    media_content_type: MediaContentType,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alt: Option<String>,
+}
+
+impl CreateMediaInput {
+    /// Media downloaded from `original_source`, which is either a public URL
+    /// or the `resource_url` of a staged upload.
+    pub fn new(original_source: &str, media_content_type: MediaContentType) -> Self {
+        Self {
+            original_source: original_source.into(),
+            media_content_type,
+            alt: None,
+        }
+    }
+
+    /// The alternative text of the media, for screen readers.
+    pub fn alt(mut self, alt: &str) -> Self {
+        self.alt = Some(alt.into());
+        self
+    }
+}
+
+/// An image, such as a variant's image.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Image {
+    /// `None` for images that aren't stored by Shopify.
+    id: Option<Id>,
+    url: String,
+    alt_text: Option<String>,
+    width: Option<i32>,
+    height: Option<i32>,
+}
+
+impl Image {
+    pub fn id(&self) -> Option<&Id> {
+        self.id.as_ref()
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The alternative text of the image, for screen readers.
+    pub fn alt_text(&self) -> Option<&String> {
+        self.alt_text.as_ref()
+    }
+
+    /// The width of the original image in pixels.
+    pub fn width(&self) -> Option<i32> {
+        self.width
+    }
+
+    /// The height of the original image in pixels.
+    pub fn height(&self) -> Option<i32> {
+        self.height
+    }
+}
+
+/// Uploads a local file through `stagedUploadsCreate` and returns the input
+/// that attaches it to a product, with [`ProductCreateMedia`] or
+/// [`ProductQueryBuilder::with_media`](crate::products::product::ProductQueryBuilder::with_media).
+///
+/// Returns [`ShopifyGqlError::InvalidUpload`] if `media_content_type` is
+/// [`MediaContentType::EXTERNAL_VIDEO`], which is referenced by URL rather
+/// than uploaded.
+pub async fn stage_media(
+    client: &ShopifyClient,
+    media_content_type: MediaContentType,
+    filename: &str,
+    mime_type: &str,
+    contents: Vec<u8>,
+) -> ShopifyResult<CreateMediaInput> {
+    let resource = match media_content_type {
+        MediaContentType::IMAGE => StagedUploadTargetGenerateUploadResource::IMAGE,
+        MediaContentType::VIDEO => StagedUploadTargetGenerateUploadResource::VIDEO,
+        MediaContentType::MODEL_3D => StagedUploadTargetGenerateUploadResource::MODEL_3D,
+        MediaContentType::EXTERNAL_VIDEO => {
+            return Err(ShopifyGqlError::InvalidUpload(
+                "external videos can't be uploaded, pass their URL to `CreateMediaInput::new`"
+                    .into(),
+            ))
+        }
+    };
+
+    // Videos and 3D models are only accepted with their size.
+    let input =
+        StagedUploadInput::new(resource, filename, mime_type).file_size(contents.len() as u64);
+
+    let target = stage_upload(client, input, contents).await?;
+    let resource_url = target.resource_url().ok_or_else(|| {
+        ShopifyGqlError::ResponseError("stagedUploadsCreate returned no resource URL".into())
+    })?;
+
+    Ok(CreateMediaInput::new(resource_url, media_content_type))
+}
+
+/// The processing status of a media file or of its preview.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum MediaStatus {
+    UPLOADED,
+    PROCESSING,
+    READY,
+    FAILED,
+}
+
+/// Where an external video is hosted.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum MediaHost {
+    YOUTUBE,
+    VIMEO,
+}
+
+/// The preview image of a [`Media`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaPreviewImage {
+    status: MediaStatus,
+
+    /// `None` until the preview is ready.
+    image: Option<Image>,
+}
+
+impl MediaPreviewImage {
+    pub fn status(&self) -> MediaStatus {
+        self.status
+    }
+
+    pub fn image(&self) -> Option<&Image> {
+        self.image.as_ref()
+    }
+}
+
+/// A file of a video or 3D model, in one of the formats it's served in.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaSource {
+    url: String,
+    mime_type: String,
+    format: String,
+
+    /// Only set for videos.
+    width: Option<i32>,
+
+    /// Only set for videos.
+    height: Option<i32>,
+
+    /// The size of the file in bytes. Only set for 3D models.
+    filesize: Option<i64>,
+}
+
+impl MediaSource {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+
+    /// The format of the file, such as `mp4` or `glb`.
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    pub fn width(&self) -> Option<i32> {
+        self.width
+    }
+
+    pub fn height(&self) -> Option<i32> {
+        self.height
+    }
+
+    pub fn filesize(&self) -> Option<i64> {
+        self.filesize
+    }
+}
+
+/// An image, video, external video or 3D model attached to a product.
+///
+/// The fields that only apply to some kinds of media are `None` on the
+/// others.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Media {
+    /// The ID of the `MediaImage`, `Video`, `ExternalVideo` or `Model3d`.
+    id: Id,
+    alt: Option<String>,
+    media_content_type: MediaContentType,
+    status: MediaStatus,
+    preview: Option<MediaPreviewImage>,
+
+    /// Only set for images.
+    image: Option<Image>,
+
+    /// Only set for videos and 3D models.
+    sources: Option<Vec<MediaSource>>,
+
+    /// Only set for external videos.
+    embed_url: Option<String>,
+
+    /// Only set for external videos.
+    host: Option<MediaHost>,
+}
+
+impl Media {
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+
+    /// The alternative text of the media, for screen readers.
+    pub fn alt(&self) -> Option<&String> {
+        self.alt.as_ref()
+    }
+
+    pub fn media_content_type(&self) -> MediaContentType {
+        self.media_content_type
+    }
+
+    pub fn status(&self) -> MediaStatus {
+        self.status
+    }
+
+    pub fn preview(&self) -> Option<&MediaPreviewImage> {
+        self.preview.as_ref()
+    }
+
+    pub fn image(&self) -> Option<&Image> {
+        self.image.as_ref()
+    }
+
+    pub fn sources(&self) -> Option<&[MediaSource]> {
+        self.sources.as_deref()
+    }
+
+    pub fn embed_url(&self) -> Option<&String> {
+        self.embed_url.as_ref()
+    }
+
+    pub fn host(&self) -> Option<MediaHost> {
+        self.host
+    }
+}
+
+/// Changes to a media file of a product, for [`ProductUpdateMedia`].
+#[derive(Debug, Serialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateMediaInput {
+    id: Id,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alt: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preview_image_source: Option<String>,
+}
+
+impl UpdateMediaInput {
+    pub fn new(id: &Id) -> Self {
+        Self {
+            id: id.clone(),
+            alt: None,
+            preview_image_source: None,
+        }
+    }
+
+    pub fn alt(mut self, alt: &str) -> Self {
+        self.alt = Some(alt.into());
+        self
+    }
+
+    /// Replaces the preview with the image at `source`, which is either a
+    /// public URL or the `resource_url` of a staged upload.
+    pub fn preview_image_source(mut self, source: &str) -> Self {
+        self.preview_image_source = Some(source.into());
+        self
+    }
+}
+
+/// Attaches media to a product, with `productCreateMedia`.
+///
+/// The media are processed asynchronously, so they are usually returned with
+/// a [`MediaStatus::UPLOADED`] or [`MediaStatus::PROCESSING`] status.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProductCreateMedia {
+    product_id: Id<resource::Product>,
+    media: Vec<CreateMediaInput>,
+}
+
+impl ProductCreateMedia {
+    pub fn new(product_id: &Id<resource::Product>) -> Self {
+        Self {
+            product_id: product_id.clone(),
+            media: vec![],
+        }
+    }
+
+    pub fn media(mut self, media: CreateMediaInput) -> Self {
+        self.media.push(media);
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        GraphQlRequest::new(format!(
+            "mutation productCreateMedia($productId: ID!, $media: [CreateMediaInput!]!) {{ productCreateMedia(productId: $productId, media: $media) {{ media {{ {} }} mediaUserErrors {{ field message code }} }} }}",
+            MEDIA_FIELDS
+        ))
+        .with_variable("productId", self.product_id.inner().clone())
+        .with_variable("media", json!(self.media))
+    }
+
+    /// Sends the mutation through `client` and returns the new media.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<Vec<Media>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::ProductCreateMedia {
+                media,
+                media_user_errors,
+            } => mutation_payload(media, media_user_errors),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+}
+
+/// Changes the alternative text or preview of media of a product, with
+/// `productUpdateMedia`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProductUpdateMedia {
+    product_id: Id<resource::Product>,
+    media: Vec<UpdateMediaInput>,
+}
+
+impl ProductUpdateMedia {
+    pub fn new(product_id: &Id<resource::Product>) -> Self {
+        Self {
+            product_id: product_id.clone(),
+            media: vec![],
+        }
+    }
+
+    pub fn media(mut self, media: UpdateMediaInput) -> Self {
+        self.media.push(media);
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        GraphQlRequest::new(format!(
+            "mutation productUpdateMedia($productId: ID!, $media: [UpdateMediaInput!]!) {{ productUpdateMedia(productId: $productId, media: $media) {{ media {{ {} }} mediaUserErrors {{ field message code }} }} }}",
+            MEDIA_FIELDS
+        ))
+        .with_variable("productId", self.product_id.inner().clone())
+        .with_variable("media", json!(self.media))
+    }
+
+    /// Sends the mutation through `client` and returns the updated media.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<Vec<Media>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::ProductUpdateMedia {
+                media,
+                media_user_errors,
+            } => mutation_payload(media, media_user_errors),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+}
+
+/// Removes media from a product, with `productDeleteMedia`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProductDeleteMedia {
+    product_id: Id<resource::Product>,
+    media_ids: Vec<Id>,
+}
+
+impl ProductDeleteMedia {
+    pub fn new(product_id: &Id<resource::Product>) -> Self {
+        Self {
+            product_id: product_id.clone(),
+            media_ids: vec![],
+        }
+    }
+
+    pub fn media(mut self, media_id: &Id) -> Self {
+        self.media_ids.push(media_id.clone());
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        GraphQlRequest::new(
+            "mutation productDeleteMedia($productId: ID!, $mediaIds: [ID!]!) { productDeleteMedia(productId: $productId, mediaIds: $mediaIds) { deletedMediaIds mediaUserErrors { field message code } } }",
+        )
+        .with_variable("productId", self.product_id.inner().clone())
+        .with_variable("mediaIds", json!(self.media_ids))
+    }
+
+    /// Sends the mutation through `client` and returns the IDs of the deleted
+    /// media.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<Vec<Id>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::ProductDeleteMedia {
+                deleted_media_ids,
+                media_user_errors,
+            } => mutation_payload(deleted_media_ids, media_user_errors),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+}
+
+/// Moves media of a product to new positions, with `productReorderMedia`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProductReorderMedia {
+    product_id: Id<resource::Product>,
+    moves: Vec<Value>,
+}
+
+impl ProductReorderMedia {
+    pub fn new(product_id: &Id<resource::Product>) -> Self {
+        Self {
+            product_id: product_id.clone(),
+            moves: vec![],
+        }
+    }
+
+    /// Moves the media with the given ID to the zero-based `new_position`.
+    pub fn move_media(mut self, media_id: &Id, new_position: u64) -> Self {
+        // `newPosition` is an `UnsignedInt64`, which Shopify expects as a
+        // string.
+        self.moves.push(json!({
+            "id": media_id,
+            "newPosition": new_position.to_string(),
+        }));
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        GraphQlRequest::new(
+            "mutation productReorderMedia($id: ID!, $moves: [MoveInput!]!) { productReorderMedia(id: $id, moves: $moves) { job { id } mediaUserErrors { field message code } } }",
+        )
+        .with_variable("id", self.product_id.inner().clone())
+        .with_variable("moves", self.moves.clone())
+    }
+
+    /// Sends the mutation through `client`.
+    ///
+    /// Shopify moves the media in a background job, so the new order may take
+    /// a moment to show up.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<()> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::ProductReorderMedia { media_user_errors } => {
+                mutation_payload(Some(()), media_user_errors)
+            }
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+}
+
+/// Attaches media of a product to its variants, with
+/// `productVariantAppendMedia`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProductVariantAppendMedia {
+    product_id: Id<resource::Product>,
+    variant_media: Vec<Value>,
+}
+
+impl ProductVariantAppendMedia {
+    pub fn new(product_id: &Id<resource::Product>) -> Self {
+        Self {
+            product_id: product_id.clone(),
+            variant_media: vec![],
+        }
+    }
+
+    /// Attaches the media with the given IDs to the variant.
+    pub fn append(mut self, variant_id: &Id<resource::ProductVariant>, media_ids: &[Id]) -> Self {
+        self.variant_media.push(json!({
+            "variantId": variant_id,
+            "mediaIds": media_ids,
+        }));
+        self
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        GraphQlRequest::new(
+            "mutation productVariantAppendMedia($productId: ID!, $variantMedia: [ProductVariantAppendMediaInput!]!) { productVariantAppendMedia(productId: $productId, variantMedia: $variantMedia) { productVariants { id } userErrors { field message code } } }",
+        )
+        .with_variable("productId", self.product_id.inner().clone())
+        .with_variable("variantMedia", self.variant_media.clone())
+    }
+
+    /// Sends the mutation through `client` and returns the IDs of the
+    /// variants.
+    pub async fn build(
+        self,
+        client: &ShopifyClient,
+    ) -> ShopifyResult<Vec<Id<resource::ProductVariant>>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::ProductVariantAppendMedia {
+                product_variants,
+                user_errors,
+            } => Ok(mutation_payload(product_variants, user_errors)?
+                .iter()
+                .map(|variant| variant.id().clone())
+                .collect()),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ShopifyConfig;
+
+    #[tokio::test]
+    async fn refuses_to_stage_external_videos() {
+        let client = ShopifyClient::new(&ShopifyConfig::init(
+            "https://example.myshopify.com/admin/api/2024-04/graphql.json",
+            "token",
+        ))
+        .unwrap();
+
+        let res = stage_media(
+            &client,
+            MediaContentType::EXTERNAL_VIDEO,
+            "video.mp4",
+            "video/mp4",
+            vec![],
+        )
+        .await;
+
+        assert!(matches!(res, Err(ShopifyGqlError::InvalidUpload(_))));
+    }
+
+    #[test]
+    fn parses_each_kind_of_media() {
+        let media: Vec<Media> = serde_json::from_value(json!([
+            {
+                "id": "gid://shopify/MediaImage/1",
+                "alt": "Front",
+                "mediaContentType": "IMAGE",
+                "status": "READY",
+                "preview": {
+                    "status": "READY",
+                    "image": { "id": null, "url": "https://cdn.shopify.com/front.jpg", "altText": "Front", "width": 800, "height": 600 }
+                },
+                "image": { "id": "gid://shopify/ImageSource/1", "url": "https://cdn.shopify.com/front.jpg", "altText": "Front", "width": 800, "height": 600 }
+            },
+            {
+                "id": "gid://shopify/Video/2",
+                "alt": null,
+                "mediaContentType": "VIDEO",
+                "status": "PROCESSING",
+                "preview": { "status": "PROCESSING", "image": null },
+                "sources": [{ "url": "https://cdn.shopify.com/demo.mp4", "mimeType": "video/mp4", "format": "mp4", "width": 1920, "height": 1080 }]
+            },
+            {
+                "id": "gid://shopify/ExternalVideo/3",
+                "alt": null,
+                "mediaContentType": "EXTERNAL_VIDEO",
+                "status": "READY",
+                "preview": null,
+                "embedUrl": "https://www.youtube.com/embed/abc",
+                "host": "YOUTUBE"
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(media[0].image().unwrap().width(), Some(800));
+        assert_eq!(media[0].preview().unwrap().status(), MediaStatus::READY);
+        assert_eq!(media[1].sources().unwrap()[0].format(), "mp4");
+        assert_eq!(media[1].preview().unwrap().image(), None);
+        assert_eq!(media[2].host(), Some(MediaHost::YOUTUBE));
+        assert_eq!(media[2].image(), None);
+    }
+
+    #[test]
+    fn passes_media_changes_as_variables() {
+        let product_id = Id::product("1").unwrap();
+        let media_id: Id = Id::parse("gid://shopify/MediaImage/2").unwrap();
+
+        let request = ProductReorderMedia::new(&product_id)
+            .move_media(&media_id, 0)
+            .request();
+        assert_eq!(
+            request.variables()["moves"],
+            json!([{ "id": "gid://shopify/MediaImage/2", "newPosition": "0" }])
+        );
+
+        let request = ProductVariantAppendMedia::new(&product_id)
+            .append(
+                &Id::product_variant("3").unwrap(),
+                std::slice::from_ref(&media_id),
+            )
+            .request();
+        assert_eq!(
+            request.variables()["variantMedia"],
+            json!([{ "variantId": "gid://shopify/ProductVariant/3", "mediaIds": ["gid://shopify/MediaImage/2"] }])
+        );
+
+        let request = ProductUpdateMedia::new(&product_id)
+            .media(UpdateMediaInput::new(&media_id).alt("Back"))
+            .request();
+        assert_eq!(
+            request.variables()["media"],
+            json!([{ "id": "gid://shopify/MediaImage/2", "alt": "Back" }])
+        );
+    }
+}
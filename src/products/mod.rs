@@ -1,12 +1,135 @@
 use serde::Deserialize;
+use serde_json::{Map, Value};
 
-pub(crate) mod product;
+use crate::common::PageInfo;
 
-pub(crate) mod product_variant;
+pub mod media;
 
-#[derive(Debug, Deserialize, PartialEq)]
+pub mod product;
+
+pub mod product_option;
+
+pub mod product_variant;
+
+/// How many nodes of a connection to fetch, and from which end.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
-pub(crate) enum ProductsConnection {
+pub enum ProductsConnection {
+    /// The first `n` nodes.
     First(u32),
+
+    /// The last `n` nodes.
     Last(u32),
+
+    /// The first `n` nodes after the given cursor.
+    After(u32, String),
+
+    /// The last `n` nodes before the given cursor.
+    Before(u32, String),
+}
+
+impl ProductsConnection {
+    /// The arguments of the connection field, such as `first: 10, after: "..."`.
+    pub(crate) fn arguments(&self) -> String {
+        match self {
+            ProductsConnection::First(n) => format!("first: {}", n),
+            ProductsConnection::Last(n) => format!("last: {}", n),
+            ProductsConnection::After(n, cursor) => {
+                format!("first: {}, after: {}", n, quote(cursor))
+            }
+            ProductsConnection::Before(n, cursor) => {
+                format!("last: {}, before: {}", n, quote(cursor))
+            }
+        }
+    }
+
+    /// The values of the variables declared by [`CONNECTION_VARIABLES`].
+    ///
+    /// Only the variables that apply are set, so the others are left out of
+    /// the query.
+    pub(crate) fn variables(&self) -> Map<String, Value> {
+        let mut vars = Map::new();
+
+        match self {
+            ProductsConnection::First(n) => {
+                vars.insert("first".into(), (*n).into());
+            }
+            ProductsConnection::Last(n) => {
+                vars.insert("last".into(), (*n).into());
+            }
+            ProductsConnection::After(n, cursor) => {
+                vars.insert("first".into(), (*n).into());
+                vars.insert("after".into(), cursor.clone().into());
+            }
+            ProductsConnection::Before(n, cursor) => {
+                vars.insert("last".into(), (*n).into());
+                vars.insert("before".into(), cursor.clone().into());
+            }
+        }
+
+        vars
+    }
+
+    /// Returns the connection for the page following this one in the same
+    /// direction, or `None` if this was the last page.
+    pub fn next_page(&self, page_info: &PageInfo) -> Option<ProductsConnection> {
+        match self {
+            ProductsConnection::First(n) | ProductsConnection::After(n, _) => {
+                match (page_info.has_next_page(), page_info.end_cursor()) {
+                    (true, Some(cursor)) => Some(ProductsConnection::After(*n, cursor.clone())),
+                    _ => None,
+                }
+            }
+
+            ProductsConnection::Last(n) | ProductsConnection::Before(n, _) => {
+                match (page_info.has_previous_page(), page_info.start_cursor()) {
+                    (true, Some(cursor)) => Some(ProductsConnection::Before(*n, cursor.clone())),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// The variable definitions of a paginated top-level connection, whose values
+/// come from [`ProductsConnection::variables`].
+pub(crate) const CONNECTION_VARIABLES: &str =
+    "$first: Int, $last: Int, $after: String, $before: String";
+
+/// The arguments that pass [`CONNECTION_VARIABLES`] on to the connection.
+pub(crate) const CONNECTION_ARGUMENTS: &str =
+    "first: $first, last: $last, after: $after, before: $before";
+
+/// Quotes and escapes `value` as a GraphQL string literal.
+///
+/// Only needed where variables can't be used, such as inside the query of a
+/// bulk operation or in the arguments of a nested connection.
+pub(crate) fn quote(value: &str) -> String {
+    // JSON strings are valid GraphQL strings.
+    serde_json::Value::from(value).to_string()
+}
+
+/// Renders a connection field that selects `fields` on each node, along with
+/// the cursors and `pageInfo` needed to fetch the next page.
+///
+/// `arguments` usually comes from [`ProductsConnection::arguments`], plus any
+/// filters the connection accepts.
+pub(crate) fn connection_query(name: &str, arguments: &str, fields: &str) -> String {
+    format!(
+        "{}({}) {{ edges {{ cursor node {{ {} }} }} pageInfo {{ hasNextPage hasPreviousPage startCursor endCursor }} }}",
+        name, arguments, fields
+    )
+}
+
+/// Renders a connection field for a bulk operation query, which fetches every
+/// node and so takes no pagination arguments or `pageInfo`.
+pub(crate) fn bulk_connection_query(name: &str, arguments: &str, fields: &str) -> String {
+    if arguments.is_empty() {
+        format!("{} {{ edges {{ node {{ {} }} }} }}", name, fields)
+    } else {
+        format!(
+            "{}({}) {{ edges {{ node {{ {} }} }} }}",
+            name, arguments, fields
+        )
+    }
 }
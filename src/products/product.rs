@@ -1,20 +1,41 @@
 #![allow(unused)]
 
-use std::{collections::HashMap, marker::PhantomData};
+use std::{
+    collections::{BTreeMap, HashMap},
+    marker::PhantomData,
+};
 
 use crate::{
-    common::{Edges, Id, Node},
-    utils::{run_query, ResponseTypes, ShopifyConfig, ShopifyGqlError, ShopifyResult},
+    collections::{Collection, CollectionQueryBuilder, CollectionQueryType},
+    common::{resource, stream_connection, Edges, Id, Node, Seo},
+    metafields::{
+        deserialize_aliased_metafields, metafield_selection, metafields_connection, Metafield,
+        MetafieldInput,
+    },
+    utils::{
+        mutation_payload, run_request, GraphQlRequest, ResponseTypes, ShopifyClient,
+        ShopifyGqlError, ShopifyResult,
+    },
 };
-use serde::{de::IntoDeserializer, Deserialize};
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::{de::IntoDeserializer, Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use super::{
-    product_variant::{ProductVariant, ProductVariantQueryBuilder, ProductVariantQueryType},
-    ProductsConnection,
+    bulk_connection_query, connection_query,
+    media::{CreateMediaInput, Image, Media, IMAGE_FIELDS, MEDIA_FIELDS},
+    product_option::{OptionCreateInput, OptionUpdate, ProductOption, PRODUCT_OPTION_FIELDS},
+    product_variant::{
+        ProductVariant, ProductVariantQueryBuilder, ProductVariantQueryType, VariantInput,
+    },
+    quote, ProductsConnection, CONNECTION_ARGUMENTS, CONNECTION_VARIABLES,
 };
 
-#[derive(Debug, Deserialize, PartialEq)]
-pub(crate) enum ProductStatus {
+/// The publishing status of a product.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum ProductStatus {
     /// The product is ready to sell and can be published to sales channels and apps.
     ACTIVE,
 
@@ -25,11 +46,15 @@ pub(crate) enum ProductStatus {
     DRAFT,
 }
 
+/// A product in the store.
+///
+/// Only the fields selected on the [`ProductQueryBuilder`] are populated; the
+/// rest are `None`.
 // NOTE: Need to update `ProductQueryBuilder` anytime a field is added/changed.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct Product {
-    id: Id,
+pub struct Product {
+    id: Id<resource::Product>,
 
     title: Option<String>,
 
@@ -37,145 +62,694 @@ pub(crate) struct Product {
 
     vendor: Option<String>,
 
+    handle: Option<String>,
+
+    description_html: Option<String>,
+
+    product_type: Option<String>,
+
+    tags: Option<Vec<String>>,
+
+    created_at: Option<DateTime<Utc>>,
+
+    updated_at: Option<DateTime<Utc>>,
+
+    /// `None` if the product isn't published to the online store, even when
+    /// the field was selected.
+    published_at: Option<DateTime<Utc>>,
+
+    seo: Option<Seo>,
+
+    template_suffix: Option<String>,
+
+    total_inventory: Option<i32>,
+
+    online_store_url: Option<String>,
+
+    options: Option<Vec<ProductOption>>,
+
     variants: Option<Edges<ProductVariant>>,
+
+    collections: Option<Edges<Collection>>,
+
+    featured_image: Option<Image>,
+
+    media: Option<Edges<Media>>,
+
+    metafields: Option<Edges<Metafield>>,
+
+    /// The metafields selected by namespace and key, by their aliases.
+    #[serde(flatten, deserialize_with = "deserialize_aliased_metafields")]
+    aliased_metafields: BTreeMap<String, Option<Metafield>>,
 }
 
 impl Product {
-    pub(crate) fn id(&self) -> &Id {
+    pub fn id(&self) -> &Id<resource::Product> {
         &self.id
     }
 
-    pub(crate) fn status(&self) -> Option<&ProductStatus> {
+    pub fn status(&self) -> Option<&ProductStatus> {
         self.status.as_ref()
     }
 
-    pub(crate) fn vendor(&self) -> Option<&String> {
+    pub fn vendor(&self) -> Option<&String> {
         self.vendor.as_ref()
     }
 
-    pub(crate) fn title(&self) -> Option<&String> {
+    pub fn title(&self) -> Option<&String> {
         self.title.as_ref()
     }
 
-    pub(crate) fn variants(&self) -> Option<&Edges<ProductVariant>> {
+    /// The unique, human-readable part of the product's URL.
+    pub fn handle(&self) -> Option<&String> {
+        self.handle.as_ref()
+    }
+
+    pub fn description_html(&self) -> Option<&String> {
+        self.description_html.as_ref()
+    }
+
+    pub fn product_type(&self) -> Option<&String> {
+        self.product_type.as_ref()
+    }
+
+    pub fn tags(&self) -> Option<&[String]> {
+        self.tags.as_deref()
+    }
+
+    pub fn created_at(&self) -> Option<&DateTime<Utc>> {
+        self.created_at.as_ref()
+    }
+
+    pub fn updated_at(&self) -> Option<&DateTime<Utc>> {
+        self.updated_at.as_ref()
+    }
+
+    pub fn published_at(&self) -> Option<&DateTime<Utc>> {
+        self.published_at.as_ref()
+    }
+
+    pub fn seo(&self) -> Option<&Seo> {
+        self.seo.as_ref()
+    }
+
+    /// The suffix of the theme template used to render the product, such as
+    /// `special` for `product.special.liquid`.
+    pub fn template_suffix(&self) -> Option<&String> {
+        self.template_suffix.as_ref()
+    }
+
+    /// The number of items in stock across every variant.
+    pub fn total_inventory(&self) -> Option<i32> {
+        self.total_inventory
+    }
+
+    /// The product's page on the online store, if it is published there.
+    pub fn online_store_url(&self) -> Option<&String> {
+        self.online_store_url.as_ref()
+    }
+
+    /// The product's options, ordered by position.
+    pub fn options(&self) -> Option<&[ProductOption]> {
+        self.options.as_deref()
+    }
+
+    pub fn variants(&self) -> Option<&Edges<ProductVariant>> {
         self.variants.as_ref()
     }
+
+    /// Returns the metafield with the given namespace and key, if it was
+    /// selected by [`ProductQueryBuilder::metafield`] or is on the fetched page of
+    /// [`ProductQueryBuilder::metafields`].
+    pub fn metafield(&self, namespace: &str, key: &str) -> Option<&Metafield> {
+        self.aliased_metafields
+            .values()
+            .flatten()
+            .chain(self.metafields.iter().flat_map(Edges::iter))
+            .find(|m| m.namespace() == namespace && m.key() == key)
+    }
+
+    pub fn metafields(&self) -> Option<&Edges<Metafield>> {
+        self.metafields.as_ref()
+    }
+
+    /// The collections that the product belongs to.
+    pub fn collections(&self) -> Option<&Edges<Collection>> {
+        self.collections.as_ref()
+    }
+
+    /// The main image of the product, or `None` if it has no images.
+    pub fn featured_image(&self) -> Option<&Image> {
+        self.featured_image.as_ref()
+    }
+
+    /// The images, videos and 3D models of the product, in order.
+    pub fn media(&self) -> Option<&Edges<Media>> {
+        self.media.as_ref()
+    }
+
+    pub(crate) fn push_variant(&mut self, variant: ProductVariant) {
+        self.variants
+            .get_or_insert_with(Edges::default)
+            .push(variant);
+    }
+
+    pub(crate) fn push_collection(&mut self, collection: Collection) {
+        self.collections
+            .get_or_insert_with(Edges::default)
+            .push(collection);
+    }
+
+    pub(crate) fn push_metafield(&mut self, metafield: Metafield) {
+        self.metafields
+            .get_or_insert_with(Edges::default)
+            .push(metafield);
+    }
+
+    pub(crate) fn push_media(&mut self, media: Media) {
+        self.media.get_or_insert_with(Edges::default).push(media);
+    }
+
+    /// Returns the variant with the given ID among the ones attached so far.
+    pub(crate) fn variant_mut(&mut self, id: &Id) -> Option<&mut ProductVariant> {
+        self.variants
+            .iter_mut()
+            .flat_map(Edges::iter_mut)
+            .find(|v| v.id().inner() == id.inner())
+    }
+}
+
+/// The keys that a `products` query can be sorted by.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum ProductSortKeys {
+    CREATED_AT,
+    ID,
+    INVENTORY_TOTAL,
+    PRODUCT_TYPE,
+    PUBLISHED_AT,
+
+    /// Sort by relevance to the search terms, when a `query` is given.
+    RELEVANCE,
+
+    TITLE,
+    UPDATED_AT,
+    VENDOR,
 }
 
 /// All possible queries and mutations on a `Product`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub(crate) enum ProductQueryType {
+pub enum ProductQueryType {
     Product,
-    ProductUpdate(Id),
+    Products(ProductsConnection),
+    ProductUpdate(Id<resource::Product>),
+    ProductCreate,
+    ProductDelete(Id<resource::Product>),
+    ProductDuplicate(Id<resource::Product>),
+    ProductChangeStatus(Id<resource::Product>),
+    ProductOptionsCreate(Id<resource::Product>),
+    ProductOptionUpdate(Id<resource::Product>),
+    ProductOptionsDelete(Id<resource::Product>),
 }
 
+/// Builds and runs a query or mutation that returns a [`Product`].
 // NOTE: This needs to be updated anytime a new field is added to `Product`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct ProductQueryBuilder {
-    id: Id,
+pub struct ProductQueryBuilder {
+    id: Id<resource::Product>,
     fields: HashMap<String, PhantomData<u8>>,
-    inputs: Option<HashMap<String, PhantomData<u8>>>,
+    inputs: Option<Map<String, Value>>,
     query_type: ProductQueryType,
+
+    /// The search `query:` of a `products` query.
+    search: Option<String>,
+
+    sort_key: Option<ProductSortKeys>,
+    reverse: bool,
+
+    /// Kept as a builder rather than rendered into `fields`, so that it can be
+    /// rendered without pagination arguments for bulk operations.
+    variants: Option<Box<ProductVariantQueryBuilder>>,
+
+    collections: Option<Box<CollectionQueryBuilder>>,
+
+    /// The connection and namespace of the selected `metafields`.
+    metafields: Option<(ProductsConnection, Option<String>)>,
+
+    /// The connection of the selected `media`.
+    media_connection: Option<ProductsConnection>,
+
+    /// The `media` of a `productCreate` mutation.
+    media: Vec<CreateMediaInput>,
+
+    /// The variants to add to a product created with
+    /// [`product_create`](Self::product_create).
+    new_variants: Vec<VariantInput>,
 }
 
 impl ProductQueryBuilder {
-    pub(crate) fn product(id: Id) -> Self {
+    fn new(id: Id<resource::Product>, query_type: ProductQueryType) -> Self {
         let mut fields = HashMap::new();
         fields.insert("id".into(), PhantomData);
 
-        let query_type = ProductQueryType::Product;
-
         ProductQueryBuilder {
             id,
             fields,
             inputs: None,
             query_type,
+            search: None,
+            sort_key: None,
+            reverse: false,
+            variants: None,
+            collections: None,
+            metafields: None,
+            media_connection: None,
+            media: vec![],
+            new_variants: vec![],
         }
     }
 
-    ///**NOTE:** Only call the `update_` methods on the returned builder.
-    pub(crate) fn product_update(id: Id) -> Self {
-        let mut fields = HashMap::new();
-        fields.insert("id".into(), PhantomData);
+    /// Fetches the product with the given ID.
+    pub fn product(id: Id<resource::Product>) -> Self {
+        Self::new(id, ProductQueryType::Product)
+    }
 
-        let mut inputs = Some(HashMap::new());
-        inputs
-            .as_mut()
-            .unwrap()
-            .insert(format!("id: \"{}\"", id.inner()), PhantomData);
+    /// Fetches a page of the store's products.
+    ///
+    /// Narrow the results down with [`search`](Self::search), order them with
+    /// [`sort_key`](Self::sort_key) and [`reverse`](Self::reverse), then run
+    /// the query with [`build_connection`](Self::build_connection) or
+    /// [`stream`](Self::stream).
+    pub fn products(conn: ProductsConnection) -> Self {
+        Self::new(Id::default(), ProductQueryType::Products(conn))
+    }
+
+    /// Updates the product with the given ID.
+    ///
+    ///**NOTE:** Only call the `update_` methods on the returned builder.
+    pub fn product_update(id: Id<resource::Product>) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("id".into(), id.inner().clone().into());
 
         let query_type = ProductQueryType::ProductUpdate(id.clone());
 
         ProductQueryBuilder {
-            id,
-            fields,
-            inputs,
-            query_type,
+            inputs: Some(inputs),
+            ..Self::new(id, query_type)
         }
     }
 
-    pub(crate) fn status(mut self) -> Self {
+    /// Creates a product and returns it.
+    ///
+    /// Set the new product's fields with the `update_` methods, and its
+    /// initial options, variants and media with
+    /// [`with_options`](Self::with_options),
+    /// [`with_variants`](Self::with_variants) and
+    /// [`with_media`](Self::with_media).
+    pub fn product_create() -> Self {
+        ProductQueryBuilder {
+            inputs: Some(Map::new()),
+            ..Self::new(Id::default(), ProductQueryType::ProductCreate)
+        }
+    }
+
+    /// Deletes the product with the given ID.
+    ///
+    /// Only the `id` of the returned product is set.
+    pub fn product_delete(id: Id<resource::Product>) -> Self {
+        Self::new(id.clone(), ProductQueryType::ProductDelete(id))
+    }
+
+    /// Copies the product with the given ID, along with its variants, under
+    /// `new_title`, and returns the copy.
+    ///
+    /// The copy keeps the original's status unless
+    /// [`update_status`](Self::update_status) is called.
+    pub fn product_duplicate(id: Id<resource::Product>, new_title: &str) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("productId".into(), id.inner().clone().into());
+        inputs.insert("newTitle".into(), new_title.into());
+
+        ProductQueryBuilder {
+            inputs: Some(inputs),
+            ..Self::new(id.clone(), ProductQueryType::ProductDuplicate(id))
+        }
+    }
+
+    /// Sets the status of the product with the given ID and returns the
+    /// product.
+    pub fn product_change_status(id: Id<resource::Product>, status: ProductStatus) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("productId".into(), id.inner().clone().into());
+        inputs.insert("status".into(), serde_json::json!(status));
+
+        ProductQueryBuilder {
+            inputs: Some(inputs),
+            ..Self::new(id.clone(), ProductQueryType::ProductChangeStatus(id))
+        }
+    }
+
+    /// Adds options to the product with the given ID and returns the product.
+    ///
+    /// Existing variants take the first value of each new option.
+    pub fn product_options_create(
+        id: Id<resource::Product>,
+        options: Vec<OptionCreateInput>,
+    ) -> Self {
+        Self::option_mutation(
+            id.clone(),
+            ProductQueryType::ProductOptionsCreate(id),
+            serde_json::json!({ "options": options }),
+        )
+    }
+
+    /// Renames, moves or changes the values of one of the product's options
+    /// and returns the product.
+    pub fn product_option_update(id: Id<resource::Product>, update: OptionUpdate) -> Self {
+        Self::option_mutation(
+            id.clone(),
+            ProductQueryType::ProductOptionUpdate(id),
+            update.variables().into(),
+        )
+    }
+
+    /// Deletes options from the product with the given ID and returns the
+    /// product.
+    pub fn product_options_delete(
+        id: Id<resource::Product>,
+        option_ids: &[Id<resource::ProductOption>],
+    ) -> Self {
+        Self::option_mutation(
+            id.clone(),
+            ProductQueryType::ProductOptionsDelete(id),
+            serde_json::json!({ "options": option_ids }),
+        )
+    }
+
+    /// Starts a `productOptions*` mutation on the product `id`, adding its
+    /// `productId` to the mutation's other variables.
+    fn option_mutation(
+        id: Id<resource::Product>,
+        query_type: ProductQueryType,
+        variables: Value,
+    ) -> Self {
+        let mut inputs = match variables {
+            Value::Object(vars) => vars,
+            _ => unreachable!(),
+        };
+        inputs.insert("productId".into(), id.inner().clone().into());
+
+        ProductQueryBuilder {
+            inputs: Some(inputs),
+            ..Self::new(id, query_type)
+        }
+    }
+
+    /// The options of a product created with
+    /// [`product_create`](Self::product_create).
+    pub fn with_options(mut self, options: Vec<OptionCreateInput>) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("productOptions".into(), serde_json::json!(options));
+        self
+    }
+
+    /// The initial variants of a product created with
+    /// [`product_create`](Self::product_create), each with its
+    /// [`option_values`](VariantInput::option_values).
+    ///
+    /// `productCreate` doesn't take variants, so they are added by a
+    /// `productVariantsBulkCreate` mutation sent once the product exists. They
+    /// replace the variant that Shopify creates from the first value of each
+    /// option. If that second mutation fails, the product is left with only
+    /// that variant.
+    pub fn with_variants(mut self, variants: Vec<VariantInput>) -> Self {
+        self.new_variants.extend(variants);
+        self
+    }
+
+    /// Attaches media to a product created with
+    /// [`product_create`](Self::product_create).
+    pub fn with_media(mut self, media: Vec<CreateMediaInput>) -> Self {
+        self.media.extend(media);
+        self
+    }
+
+    /// Filters a `products` query using Shopify's search syntax, such as
+    /// `vendor:TEST AND status:active`.
+    pub fn search(mut self, query: &str) -> Self {
+        self.search = Some(query.into());
+        self
+    }
+
+    /// Sorts the results of a `products` query.
+    pub fn sort_key(mut self, sort_key: ProductSortKeys) -> Self {
+        self.sort_key = Some(sort_key);
+        self
+    }
+
+    /// Reverses the order of the results of a `products` query.
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    pub fn status(mut self) -> Self {
         self.fields.insert("status".into(), PhantomData);
         self
     }
 
-    pub(crate) fn update_status(mut self, status: ProductStatus) -> Self {
-        let status = format!("status: {:?}", status);
+    pub fn update_status(mut self, status: ProductStatus) -> Self {
+        // `productDuplicate` calls it `newStatus`.
+        let key = match self.query_type {
+            ProductQueryType::ProductDuplicate(_) => "newStatus",
+            _ => "status",
+        };
 
-        self.inputs.as_mut().unwrap().insert(status, PhantomData);
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert(key.into(), format!("{:?}", status).into());
         self
     }
 
-    pub(crate) fn vendor(mut self) -> Self {
+    pub fn vendor(mut self) -> Self {
         self.fields.insert("vendor".into(), PhantomData);
         self
     }
 
-    pub(crate) fn update_vendor(mut self, vendor: &str) -> Self {
-        let vendor = format!("vendor: {:?}", vendor);
-
-        self.inputs.as_mut().unwrap().insert(vendor, PhantomData);
+    pub fn update_vendor(mut self, vendor: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("vendor".into(), vendor.into());
         self
     }
 
-    pub(crate) fn title(mut self) -> Self {
+    pub fn title(mut self) -> Self {
         self.fields.insert("title".into(), PhantomData);
         self
     }
 
-    pub(crate) fn update_title(mut self, title: &str) -> Self {
-        let title = format!("title: {:?}", title);
+    pub fn update_title(mut self, title: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("title".into(), title.into());
+        self
+    }
+
+    /// Selects the product's options along with all of their values.
+    pub fn options(mut self) -> Self {
+        self.fields
+            .insert(PRODUCT_OPTION_FIELDS.into(), PhantomData);
+        self
+    }
+
+    pub fn handle(mut self) -> Self {
+        self.fields.insert("handle".into(), PhantomData);
+        self
+    }
+
+    pub fn update_handle(mut self, handle: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("handle".into(), handle.into());
+        self
+    }
+
+    pub fn description_html(mut self) -> Self {
+        self.fields.insert("descriptionHtml".into(), PhantomData);
+        self
+    }
+
+    pub fn update_description_html(mut self, description_html: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("descriptionHtml".into(), description_html.into());
+        self
+    }
+
+    pub fn product_type(mut self) -> Self {
+        self.fields.insert("productType".into(), PhantomData);
+        self
+    }
+
+    pub fn update_product_type(mut self, product_type: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("productType".into(), product_type.into());
+        self
+    }
+
+    pub fn tags(mut self) -> Self {
+        self.fields.insert("tags".into(), PhantomData);
+        self
+    }
+
+    /// Replaces all of the product's tags.
+    pub fn update_tags(mut self, tags: &[&str]) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("tags".into(), tags.into());
+        self
+    }
+
+    /// Selects the metafield with the given namespace and key, which
+    /// [`Product::metafield`] then returns.
+    pub fn metafield(mut self, namespace: &str, key: &str) -> Self {
+        self.fields
+            .insert(metafield_selection(namespace, key), PhantomData);
+        self
+    }
+
+    /// Selects a page of the product's metafields, optionally only the ones in
+    /// `namespace`.
+    ///
+    /// In bulk operations, every metafield is fetched and `conn` is ignored.
+    pub fn metafields(mut self, conn: ProductsConnection, namespace: Option<&str>) -> Self {
+        self.metafields = Some((conn, namespace.map(String::from)));
+        self
+    }
+
+    /// Creates or updates metafields of the product.
+    pub fn update_metafields(mut self, metafields: Vec<MetafieldInput>) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("metafields".into(), serde_json::json!(metafields));
+        self
+    }
+
+    pub fn featured_image(mut self) -> Self {
+        self.fields
+            .insert(format!("featuredImage {{ {} }}", IMAGE_FIELDS), PhantomData);
+        self
+    }
+
+    /// Selects a page of the product's images, videos and 3D models.
+    ///
+    /// In bulk operations, all of the product's media is fetched.
+    pub fn media(mut self, conn: ProductsConnection) -> Self {
+        self.media_connection = Some(conn);
+        self
+    }
+
+    pub fn created_at(mut self) -> Self {
+        self.fields.insert("createdAt".into(), PhantomData);
+        self
+    }
+
+    pub fn updated_at(mut self) -> Self {
+        self.fields.insert("updatedAt".into(), PhantomData);
+        self
+    }
+
+    pub fn published_at(mut self) -> Self {
+        self.fields.insert("publishedAt".into(), PhantomData);
+        self
+    }
+
+    pub fn seo(mut self) -> Self {
+        self.fields
+            .insert("seo { title description }".into(), PhantomData);
+        self
+    }
+
+    pub fn update_seo(mut self, seo: Seo) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("seo".into(), serde_json::to_value(seo).unwrap());
+        self
+    }
+
+    pub fn template_suffix(mut self) -> Self {
+        self.fields.insert("templateSuffix".into(), PhantomData);
+        self
+    }
+
+    pub fn update_template_suffix(mut self, template_suffix: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("templateSuffix".into(), template_suffix.into());
+        self
+    }
+
+    pub fn total_inventory(mut self) -> Self {
+        self.fields.insert("totalInventory".into(), PhantomData);
+        self
+    }
 
-        self.inputs.as_mut().unwrap().insert(title, PhantomData);
+    pub fn online_store_url(mut self) -> Self {
+        self.fields.insert("onlineStoreUrl".into(), PhantomData);
         self
     }
 
-    pub(crate) fn variants(mut self, variants_query: ProductVariantQueryBuilder) -> Self {
+    /// Selects the product's variants.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `variants_query` was not created with
+    /// [`ProductVariantQueryBuilder::product_variants`].
+    pub fn variants(mut self, variants_query: ProductVariantQueryBuilder) -> Self {
         // Make sure the query is a `productVariants` query
-        let var_str = match variants_query.query_type() {
-            ProductVariantQueryType::ProductVariants(conn) => match conn {
-                ProductsConnection::First(n) => {
-                    format!(
-                        "variants(first: {}) {{ edges {{ node {{ {} }} }} }}",
-                        n,
-                        variants_query.fields().join("\n,")
-                    )
-                }
-                ProductsConnection::Last(n) => {
-                    format!(
-                        "variants(last: {}) {{ edges {{ node {{ {} }} }} }}",
-                        n,
-                        variants_query.fields().join("\n,")
-                    )
-                }
-            },
+        if !matches!(
+            variants_query.query_type(),
+            ProductVariantQueryType::ProductVariants(_)
+        ) {
+            panic!("`variants` requires a `ProductVariantQueryBuilder::product_variants` query")
+        }
 
-            _ => panic!("ERROR REPLACE THIS"),
-        };
+        self.variants = Some(Box::new(variants_query));
+        self
+    }
+
+    /// Selects the collections that the product belongs to.
+    ///
+    /// In bulk operations, every matching collection is fetched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `collections_query` was not created with
+    /// [`CollectionQueryBuilder::collections`].
+    pub fn collections(mut self, collections_query: CollectionQueryBuilder) -> Self {
+        if !matches!(
+            collections_query.query_type(),
+            CollectionQueryType::Collections(_)
+        ) {
+            panic!("`collections` requires a `CollectionQueryBuilder::collections` query")
+        }
 
-        self.fields.insert(var_str, PhantomData);
+        self.collections = Some(Box::new(collections_query));
         self
     }
 
@@ -183,42 +757,625 @@ impl ProductQueryBuilder {
         self.fields.keys().map(|v| v.as_str()).collect()
     }
 
-    pub(crate) fn inputs(&self) -> Option<Vec<&str>> {
-        self.inputs
-            .as_ref()
-            .map_or(None, |m| Some(m.keys().map(|v| v.as_str()).collect()))
+    pub(crate) fn inputs(&self) -> Option<&Map<String, Value>> {
+        self.inputs.as_ref()
+    }
+
+    /// Renders the selected fields, including nested connections.
+    ///
+    /// With `bulk` set, nested connections are rendered without pagination
+    /// arguments or `pageInfo`, as bulk operations require.
+    pub(crate) fn selection(&self, bulk: bool) -> String {
+        let mut fields: Vec<String> = self.fields().into_iter().map(String::from).collect();
+
+        if let Some(variants) = &self.variants {
+            fields.push(match variants.query_type() {
+                ProductVariantQueryType::ProductVariants(_) if bulk => {
+                    bulk_connection_query("variants", "", &variants.selection(true))
+                }
+
+                ProductVariantQueryType::ProductVariants(conn) => {
+                    connection_query("variants", &conn.arguments(), &variants.selection(false))
+                }
+
+                _ => unreachable!(),
+            });
+        }
+
+        if let Some(collections) = &self.collections {
+            fields.push(match collections.query_type() {
+                CollectionQueryType::Collections(_) if bulk => bulk_connection_query(
+                    "collections",
+                    &collections.nested_arguments(None),
+                    &collections.selection(),
+                ),
+
+                CollectionQueryType::Collections(conn) => connection_query(
+                    "collections",
+                    &collections.nested_arguments(Some(conn)),
+                    &collections.selection(),
+                ),
+
+                _ => unreachable!(),
+            });
+        }
+
+        if let Some((conn, namespace)) = &self.metafields {
+            let conn = if bulk { None } else { Some(conn) };
+            fields.push(metafields_connection(conn, namespace.as_deref()));
+        }
+
+        if let Some(conn) = &self.media_connection {
+            fields.push(if bulk {
+                bulk_connection_query("media", "", MEDIA_FIELDS)
+            } else {
+                connection_query("media", &conn.arguments(), MEDIA_FIELDS)
+            });
+        }
+
+        fields.join("\n,")
     }
 
-    pub(crate) async fn build(self, config: ShopifyConfig) -> ShopifyResult<Product> {
-        let fields = self.fields().join("\n,");
-        let inputs = self.inputs();
+    /// The values of the `$query`, `$sortKey` and `$reverse` variables of a
+    /// `products` query.
+    fn filter_variables(&self) -> Map<String, Value> {
+        let mut vars = Map::new();
 
-        let query = match &self.query_type {
-            ProductQueryType::Product => {
-                format!(
-                    "query {{ product(id: \"{}\") {{ {} }} }}",
-                    self.id.inner(),
-                    fields
+        if let Some(search) = &self.search {
+            vars.insert("query".into(), search.clone().into());
+        }
+        if let Some(sort_key) = &self.sort_key {
+            vars.insert("sortKey".into(), format!("{:?}", sort_key).into());
+        }
+        if self.reverse {
+            vars.insert("reverse".into(), true.into());
+        }
+
+        vars
+    }
+
+    /// The `query`, `sortKey` and `reverse` arguments of a bulk `products`
+    /// query, which can't take variables.
+    fn filter_arguments(&self) -> Vec<String> {
+        let mut args = vec![];
+
+        if let Some(search) = &self.search {
+            args.push(format!("query: {}", quote(search)));
+        }
+        if let Some(sort_key) = &self.sort_key {
+            args.push(format!("sortKey: {:?}", sort_key));
+        }
+        if self.reverse {
+            args.push("reverse: true".into());
+        }
+
+        args
+    }
+
+    /// Renders a [`products`](Self::products) query as a bulk operation
+    /// query, which walks the whole connection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the builder was not created with
+    /// [`products`](Self::products).
+    pub(crate) fn bulk_query(&self) -> String {
+        match &self.query_type {
+            ProductQueryType::Products(_) => format!(
+                "{{ {} }}",
+                bulk_connection_query(
+                    "products",
+                    &self.filter_arguments().join(", "),
+                    &self.selection(true)
                 )
-            }
+            ),
+
+            _ => panic!("bulk operations require a `ProductQueryBuilder::products` query"),
+        }
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let fields = self.selection(false);
 
-            // TODO: Handle user errors
-            ProductQueryType::ProductUpdate(id) => {
-                format!(
-                    "mutation {{ productUpdate(input: {{ {} }}) {{ product {{ {} }} }}  }}",
-                    inputs.unwrap().join("\n,"),
-                    fields
+        match &self.query_type {
+            ProductQueryType::Product => GraphQlRequest::new(format!(
+                "query product($id: ID!) {{ product(id: $id) {{ {} }} }}",
+                fields
+            ))
+            .with_variable("id", self.id.inner().clone()),
+
+            ProductQueryType::Products(conn) => GraphQlRequest::new(format!(
+                "query products({}, $query: String, $sortKey: ProductSortKeys, $reverse: Boolean) {{ {} }}",
+                CONNECTION_VARIABLES,
+                connection_query(
+                    "products",
+                    &format!(
+                        "{}, query: $query, sortKey: $sortKey, reverse: $reverse",
+                        CONNECTION_ARGUMENTS
+                    ),
+                    &fields
                 )
-            }
-        };
+            ))
+            .with_variables(conn.variables())
+            .with_variables(self.filter_variables()),
+
+            ProductQueryType::ProductUpdate(_) => GraphQlRequest::new(format!(
+                "mutation productUpdate($input: ProductInput!) {{ productUpdate(input: $input) {{ product {{ {} }} userErrors {{ field message }} }} }}",
+                fields
+            ))
+            .with_variable("input", self.inputs().cloned().unwrap_or_default()),
+
+            ProductQueryType::ProductCreate => GraphQlRequest::new(format!(
+                "mutation productCreate($input: ProductInput!, $media: [CreateMediaInput!]) {{ productCreate(input: $input, media: $media) {{ product {{ {} }} userErrors {{ field message }} }} }}",
+                fields
+            ))
+            .with_variable("input", self.inputs().cloned().unwrap_or_default())
+            .with_variable("media", serde_json::json!(self.media)),
+
+            ProductQueryType::ProductDelete(id) => GraphQlRequest::new(
+                "mutation productDelete($input: ProductDeleteInput!) { productDelete(input: $input) { deletedProductId userErrors { field message } } }",
+            )
+            .with_variable("input", serde_json::json!({ "id": id })),
+
+            ProductQueryType::ProductDuplicate(_) => GraphQlRequest::new(format!(
+                "mutation productDuplicate($productId: ID!, $newTitle: String!, $newStatus: ProductStatus) {{ productDuplicate(productId: $productId, newTitle: $newTitle, newStatus: $newStatus) {{ newProduct {{ {} }} userErrors {{ field message }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs().cloned().unwrap_or_default()),
+
+            ProductQueryType::ProductChangeStatus(_) => GraphQlRequest::new(format!(
+                "mutation productChangeStatus($productId: ID!, $status: ProductStatus!) {{ productChangeStatus(productId: $productId, status: $status) {{ product {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs().cloned().unwrap_or_default()),
+
+            ProductQueryType::ProductOptionsCreate(_) => GraphQlRequest::new(format!(
+                "mutation productOptionsCreate($productId: ID!, $options: [OptionCreateInput!]!) {{ productOptionsCreate(productId: $productId, options: $options) {{ product {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs().cloned().unwrap_or_default()),
+
+            ProductQueryType::ProductOptionUpdate(_) => GraphQlRequest::new(format!(
+                "mutation productOptionUpdate($productId: ID!, $option: OptionUpdateInput!, $optionValuesToAdd: [OptionValueCreateInput!], $optionValuesToUpdate: [OptionValueUpdateInput!], $optionValuesToDelete: [ID!]) {{ productOptionUpdate(productId: $productId, option: $option, optionValuesToAdd: $optionValuesToAdd, optionValuesToUpdate: $optionValuesToUpdate, optionValuesToDelete: $optionValuesToDelete) {{ product {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs().cloned().unwrap_or_default()),
+
+            ProductQueryType::ProductOptionsDelete(_) => GraphQlRequest::new(format!(
+                "mutation productOptionsDelete($productId: ID!, $options: [ID!]!) {{ productOptionsDelete(productId: $productId, options: $options) {{ product {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs().cloned().unwrap_or_default()),
+        }
+    }
+
+    /// The `productVariantsBulkCreate` mutation that adds the
+    /// [`with_variants`](Self::with_variants) of a new product, returning the
+    /// product with the selected fields.
+    fn new_variants_request(&self, product_id: &Id<resource::Product>) -> GraphQlRequest {
+        GraphQlRequest::new(format!(
+            "mutation productVariantsBulkCreate($productId: ID!, $variants: [ProductVariantsBulkInput!]!, $strategy: ProductVariantsBulkCreateStrategy) {{ productVariantsBulkCreate(productId: $productId, variants: $variants, strategy: $strategy) {{ product {{ {} }} userErrors {{ field message code }} }} }}",
+            self.selection(false)
+        ))
+        .with_variable("productId", product_id.inner().clone())
+        .with_variable(
+            "variants",
+            self.new_variants
+                .iter()
+                .map(VariantInput::bulk_input)
+                .collect::<Vec<_>>(),
+        )
+        .with_variable("strategy", "REMOVE_STANDALONE_VARIANT")
+    }
+
+    /// Sends the query through `client` and returns the product.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<Product> {
+        let product = self.send(client).await?;
+
+        if self.new_variants.is_empty() {
+            return Ok(product);
+        }
+
+        let res = run_request(client, &self.new_variants_request(product.id()))
+            .await?
+            .into_data()?;
+        match res {
+            ResponseTypes::ProductVariantsBulkCreate {
+                product,
+                user_errors,
+                ..
+            } => mutation_payload(product, user_errors),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
 
-        let res = run_query(config, query).await?;
-        match res.data {
+    /// Sends the query or mutation itself, without the follow-up of
+    /// [`with_variants`](Self::with_variants).
+    async fn send(&self, client: &ShopifyClient) -> ShopifyResult<Product> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
             ResponseTypes::Product(p) => Ok(p),
 
-            ResponseTypes::ProductUpdate { product } => Ok(product),
+            ResponseTypes::ProductUpdate {
+                product,
+                user_errors,
+            }
+            | ResponseTypes::ProductCreate {
+                product,
+                user_errors,
+            }
+            | ResponseTypes::ProductDuplicate {
+                new_product: product,
+                user_errors,
+            }
+            | ResponseTypes::ProductChangeStatus {
+                product,
+                user_errors,
+            }
+            | ResponseTypes::ProductOptionsCreate {
+                product,
+                user_errors,
+            }
+            | ResponseTypes::ProductOptionUpdate {
+                product,
+                user_errors,
+            }
+            | ResponseTypes::ProductOptionsDelete {
+                product,
+                user_errors,
+            } => mutation_payload(product, user_errors),
+
+            ResponseTypes::ProductDelete {
+                deleted_product_id,
+                user_errors,
+            } => mutation_payload(deleted_product_id, user_errors).map(|id| Product {
+                id,
+                ..Product::default()
+            }),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Sends a [`products`](Self::products) query through `client` and returns
+    /// the page of products.
+    pub async fn build_connection(self, client: &ShopifyClient) -> ShopifyResult<Edges<Product>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::Products(p) => Ok(p),
 
             _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
         }
     }
+
+    /// Runs a [`products`](Self::products) query page by page, yielding every
+    /// matching product until the connection is exhausted.
+    ///
+    /// Yields a single [`ShopifyGqlError::InvalidQuery`] if the builder was
+    /// not created with [`products`](Self::products).
+    pub fn stream(self, client: &ShopifyClient) -> impl Stream<Item = ShopifyResult<Product>> {
+        let first = match &self.query_type {
+            ProductQueryType::Products(conn) => Ok(conn.clone()),
+            _ => Err(ShopifyGqlError::InvalidQuery(
+                "`stream` requires a `ProductQueryBuilder::products` query".into(),
+            )),
+        };
+
+        stream_connection(
+            self,
+            first,
+            client,
+            move |page, conn| page.query_type = ProductQueryType::Products(conn),
+            |page, client| async move { page.build_connection(&client).await },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        products::{media::MediaContentType, product_option::SelectedOption},
+        utils::ShopifyConfig,
+    };
+    use futures::StreamExt;
+
+    #[test]
+    fn passes_inputs_as_variables() {
+        let request = ProductQueryBuilder::product_update(Id::product("1").unwrap())
+            .update_title("A \"quoted\" title")
+            .title()
+            .request();
+
+        assert!(!request.query().contains("quoted"));
+        assert_eq!(
+            request.variables()["input"],
+            serde_json::json!({ "id": "gid://shopify/Product/1", "title": "A \"quoted\" title" })
+        );
+    }
+
+    #[test]
+    fn parses_product_fields() {
+        let product: Product = serde_json::from_value(serde_json::json!({
+            "id": "gid://shopify/Product/1",
+            "handle": "shirt",
+            "tags": ["summer", "sale"],
+            "createdAt": "2023-02-14T18:30:00Z",
+            "publishedAt": null,
+            "seo": { "title": "Shirt", "description": null },
+            "totalInventory": 12
+        }))
+        .unwrap();
+
+        assert_eq!(product.handle().unwrap(), "shirt");
+        assert_eq!(product.tags().unwrap(), ["summer", "sale"]);
+        assert_eq!(
+            product.created_at().unwrap().to_rfc3339(),
+            "2023-02-14T18:30:00+00:00"
+        );
+        assert!(product.published_at().is_none());
+        assert_eq!(product.seo().unwrap().title().unwrap(), "Shirt");
+        assert_eq!(product.total_inventory(), Some(12));
+
+        let request = ProductQueryBuilder::product_update(Id::product("1").unwrap())
+            .update_tags(&["summer"])
+            .update_seo(Seo::new(None, Some("Light cotton")))
+            .request();
+        assert_eq!(
+            request.variables()["input"],
+            serde_json::json!({
+                "id": "gid://shopify/Product/1",
+                "tags": ["summer"],
+                "seo": { "description": "Light cotton" }
+            })
+        );
+    }
+
+    #[test]
+    fn passes_option_changes_as_variables() {
+        let option_id: Id<resource::ProductOption> =
+            "gid://shopify/ProductOption/3".parse().unwrap();
+        let value_id: Id<resource::ProductOptionValue> =
+            "gid://shopify/ProductOptionValue/4".parse().unwrap();
+
+        let request = ProductQueryBuilder::product_option_update(
+            Id::product("1").unwrap(),
+            OptionUpdate::new(option_id)
+                .name("Colour")
+                .add_values(&["Green"])
+                .rename_value(value_id, "Crimson"),
+        )
+        .options()
+        .request();
+
+        assert_eq!(
+            Value::Object(request.variables().clone()),
+            serde_json::json!({
+                "productId": "gid://shopify/Product/1",
+                "option": { "id": "gid://shopify/ProductOption/3", "name": "Colour" },
+                "optionValuesToAdd": [{ "name": "Green" }],
+                "optionValuesToUpdate": [{ "id": "gid://shopify/ProductOptionValue/4", "name": "Crimson" }]
+            })
+        );
+
+        let request = ProductQueryBuilder::product_options_create(
+            Id::product("1").unwrap(),
+            vec![OptionCreateInput::new("Size", &["S", "M"]).position(2)],
+        )
+        .request();
+
+        assert_eq!(
+            request.variables()["options"],
+            serde_json::json!([{ "name": "Size", "position": 2, "values": [{ "name": "S" }, { "name": "M" }] }])
+        );
+    }
+
+    #[test]
+    fn creates_products_with_variants_and_media() {
+        let builder = ProductQueryBuilder::product_create()
+            .update_title("Shirt")
+            .with_options(vec![OptionCreateInput::new("Color", &["Red", "Blue"])])
+            .with_variants(vec![
+                VariantInput::new()
+                    .option_values(&[SelectedOption::new("Color", "Red")])
+                    .price("19.99".parse().unwrap()),
+                VariantInput::new()
+                    .option_values(&[SelectedOption::new("Color", "Blue")])
+                    .price("21.99".parse().unwrap()),
+            ])
+            .with_media(vec![CreateMediaInput::new(
+                "https://example.com/shirt.png",
+                MediaContentType::IMAGE,
+            )
+            .alt("A red shirt")])
+            .title();
+
+        // `productCreate` only takes the options; the variants follow in a
+        // second mutation.
+        let request = builder.request();
+        assert_eq!(
+            Value::Object(request.variables().clone()),
+            serde_json::json!({
+                "input": {
+                    "title": "Shirt",
+                    "productOptions": [{
+                        "name": "Color",
+                        "values": [{ "name": "Red" }, { "name": "Blue" }]
+                    }]
+                },
+                "media": [{
+                    "originalSource": "https://example.com/shirt.png",
+                    "mediaContentType": "IMAGE",
+                    "alt": "A red shirt"
+                }]
+            })
+        );
+
+        let request = builder.new_variants_request(&Id::product("1").unwrap());
+        assert!(request
+            .query()
+            .contains("productVariantsBulkCreate(productId: $productId, variants: $variants, strategy: $strategy) { product {"));
+        assert_eq!(
+            Value::Object(request.variables().clone()),
+            serde_json::json!({
+                "productId": "gid://shopify/Product/1",
+                "variants": [
+                    { "optionValues": [{ "optionName": "Color", "name": "Red" }], "price": "19.99" },
+                    { "optionValues": [{ "optionName": "Color", "name": "Blue" }], "price": "21.99" }
+                ],
+                "strategy": "REMOVE_STANDALONE_VARIANT"
+            })
+        );
+
+        let request =
+            ProductQueryBuilder::product_duplicate(Id::product("1").unwrap(), "Shirt (copy)")
+                .update_status(ProductStatus::DRAFT)
+                .request();
+        assert_eq!(request.variables()["newStatus"], "DRAFT");
+    }
+
+    #[test]
+    fn selects_collections_with_inline_filters() {
+        let request = ProductQueryBuilder::product(Id::product("1").unwrap())
+            .collections(
+                CollectionQueryBuilder::collections(ProductsConnection::First(5))
+                    .search("collection_type:smart")
+                    .title(),
+            )
+            .request();
+
+        assert!(request.query().contains(
+            "collections(first: 5, query: \"collection_type:smart\") { edges { cursor node { "
+        ));
+    }
+
+    #[test]
+    fn selects_media_and_featured_image() {
+        let request = ProductQueryBuilder::product(Id::product("1").unwrap())
+            .featured_image()
+            .media(ProductsConnection::First(10))
+            .request();
+
+        assert!(request
+            .query()
+            .contains("featuredImage { id url altText width height }"));
+        assert!(request.query().contains("media(first: 10) { edges"));
+        assert!(request.query().contains("... on Model3d { sources"));
+
+        let product: Product = serde_json::from_value(serde_json::json!({
+            "id": "gid://shopify/Product/1",
+            "featuredImage": null,
+            "media": {
+                "edges": [{
+                    "cursor": "a",
+                    "node": {
+                        "id": "gid://shopify/Model3d/4",
+                        "alt": "Chair",
+                        "mediaContentType": "MODEL_3D",
+                        "status": "READY",
+                        "preview": null,
+                        "sources": [{ "url": "https://cdn.shopify.com/chair.glb", "mimeType": "model/gltf-binary", "format": "glb", "filesize": 1024 }]
+                    }
+                }],
+                "pageInfo": { "hasNextPage": false, "hasPreviousPage": false, "startCursor": "a", "endCursor": "a" }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(product.featured_image(), None);
+        let media = product.media().unwrap().iter().next().unwrap();
+        assert_eq!(media.sources().unwrap()[0].filesize(), Some(1024));
+    }
+
+    #[tokio::test]
+    async fn streams_only_products_queries() {
+        let client = ShopifyClient::new(&ShopifyConfig::init(
+            "https://example.myshopify.com/admin/api/2024-04/graphql.json",
+            "token",
+        ))
+        .unwrap();
+
+        let results: Vec<_> = ProductQueryBuilder::product(Id::product("1").unwrap())
+            .stream(&client)
+            .collect()
+            .await;
+
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(ShopifyGqlError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn reads_aliased_metafields() {
+        let request = ProductQueryBuilder::product(Id::product("1").unwrap())
+            .metafield("custom", "care")
+            .request();
+        assert!(request
+            .query()
+            .contains("metafield_637573746f6d_63617265: metafield("));
+
+        let product: Product = serde_json::from_value(serde_json::json!({
+            "__typename": "Product",
+            "id": "gid://shopify/Product/1",
+            "metafield_637573746f6d_63617265": {
+                "id": "gid://shopify/Metafield/9",
+                "namespace": "custom",
+                "key": "care",
+                "type": "single_line_text_field",
+                "value": "Wash cold"
+            },
+            "metafield_637573746f6d_77617272616e7479": null
+        }))
+        .unwrap();
+
+        assert_eq!(
+            product.metafield("custom", "care").unwrap().value(),
+            "Wash cold"
+        );
+        assert_eq!(product.metafield("custom", "warranty"), None);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let json = serde_json::json!({
+            "id": "gid://shopify/Product/1",
+            "title": "Shirt",
+            "status": "ACTIVE",
+            "variants": {
+                "edges": [{
+                    "cursor": "abc",
+                    "node": {
+                        "id": "gid://shopify/ProductVariant/2",
+                        "price": "19.90",
+                        "weightMeasurement": {
+                            "measurement": { "weight": { "value": 1.5, "unit": "KILOGRAMS" } }
+                        }
+                    }
+                }]
+            }
+        });
+
+        let product: Product = serde_json::from_value(json).unwrap();
+        let reparsed: Product =
+            serde_json::from_value(serde_json::to_value(&product).unwrap()).unwrap();
+
+        assert_eq!(reparsed, product);
+        assert_eq!(reparsed.variants().unwrap().get_node(0).weight(), Some(1.5));
+        assert_eq!(
+            serde_json::to_value(&reparsed).unwrap()["variants"]["edges"][0]["node"]["price"],
+            "19.90"
+        );
+    }
+
+    #[test]
+    fn leaves_out_unset_connection_variables() {
+        let request = ProductQueryBuilder::products(ProductsConnection::After(10, "abc".into()))
+            .search("title:\"Red\"")
+            .request();
+
+        assert_eq!(
+            Value::Object(request.variables().clone()),
+            serde_json::json!({ "first": 10, "after": "abc", "query": "title:\"Red\"" })
+        );
+    }
 }
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::common::{resource, Id};
+
+/// The fields selected whenever a product's `options` are fetched.
+pub(crate) const PRODUCT_OPTION_FIELDS: &str =
+    "options { id name position values optionValues { id name hasVariants } }";
+
+/// A product property that varies between variants, such as `Color` or
+/// `Size`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductOption {
+    id: Id<resource::ProductOption>,
+    name: String,
+    position: i32,
+
+    /// The names of the values that are used by at least one variant.
+    values: Vec<String>,
+
+    option_values: Vec<ProductOptionValue>,
+}
+
+impl ProductOption {
+    pub fn id(&self) -> &Id<resource::ProductOption> {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The option's position among the product's options, starting at 1.
+    pub fn position(&self) -> i32 {
+        self.position
+    }
+
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+
+    /// Every value of the option, including ones that no variant uses yet.
+    pub fn option_values(&self) -> &[ProductOptionValue] {
+        &self.option_values
+    }
+}
+
+/// A value of a [`ProductOption`], such as `Red`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductOptionValue {
+    id: Id<resource::ProductOptionValue>,
+    name: String,
+    has_variants: bool,
+}
+
+impl ProductOptionValue {
+    pub fn id(&self) -> &Id<resource::ProductOptionValue> {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `true` if at least one variant uses the value.
+    pub fn has_variants(&self) -> bool {
+        self.has_variants
+    }
+}
+
+/// The value a variant has for one of the product's options.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct SelectedOption {
+    name: String,
+    value: String,
+}
+
+impl SelectedOption {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    /// The name of the option, such as `Color`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// A new option for
+/// [`ProductQueryBuilder::product_options_create`](super::product::ProductQueryBuilder::product_options_create).
+#[derive(Debug, Serialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionCreateInput {
+    name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<i32>,
+
+    values: Vec<OptionValueInput>,
+}
+
+impl OptionCreateInput {
+    /// An option called `name` with the given values.
+    pub fn new(name: &str, values: &[&str]) -> Self {
+        Self {
+            name: name.into(),
+            position: None,
+            values: values.iter().map(|v| OptionValueInput::new(v)).collect(),
+        }
+    }
+
+    /// Where to insert the option among the product's options, starting at 1.
+    /// Defaults to the end.
+    pub fn position(mut self, position: i32) -> Self {
+        self.position = Some(position);
+        self
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone)]
+struct OptionValueInput {
+    name: String,
+}
+
+impl OptionValueInput {
+    fn new(name: &str) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+/// Changes to an existing option for
+/// [`ProductQueryBuilder::product_option_update`](super::product::ProductQueryBuilder::product_option_update).
+#[derive(Debug, PartialEq, Clone)]
+pub struct OptionUpdate {
+    option: Map<String, Value>,
+    values_to_add: Vec<OptionValueInput>,
+    values_to_update: Vec<Value>,
+    values_to_delete: Vec<Id<resource::ProductOptionValue>>,
+}
+
+impl OptionUpdate {
+    /// Updates the option with the given ID.
+    pub fn new(option_id: Id<resource::ProductOption>) -> Self {
+        let mut option = Map::new();
+        option.insert("id".into(), option_id.inner().clone().into());
+
+        Self {
+            option,
+            values_to_add: vec![],
+            values_to_update: vec![],
+            values_to_delete: vec![],
+        }
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.option.insert("name".into(), name.into());
+        self
+    }
+
+    /// Moves the option to `position`, starting at 1.
+    pub fn position(mut self, position: i32) -> Self {
+        self.option.insert("position".into(), position.into());
+        self
+    }
+
+    /// Adds new values to the option.
+    pub fn add_values(mut self, names: &[&str]) -> Self {
+        self.values_to_add
+            .extend(names.iter().map(|n| OptionValueInput::new(n)));
+        self
+    }
+
+    /// Renames one of the option's values.
+    pub fn rename_value(mut self, value_id: Id<resource::ProductOptionValue>, name: &str) -> Self {
+        self.values_to_update
+            .push(json!({ "id": value_id, "name": name }));
+        self
+    }
+
+    /// Removes values from the option.
+    pub fn delete_values(mut self, value_ids: &[Id<resource::ProductOptionValue>]) -> Self {
+        self.values_to_delete.extend_from_slice(value_ids);
+        self
+    }
+
+    /// The variables of a `productOptionUpdate` mutation, except `$productId`.
+    pub(crate) fn variables(&self) -> Map<String, Value> {
+        let mut vars = Map::new();
+        vars.insert("option".into(), self.option.clone().into());
+
+        if !self.values_to_add.is_empty() {
+            vars.insert("optionValuesToAdd".into(), json!(self.values_to_add));
+        }
+        if !self.values_to_update.is_empty() {
+            vars.insert(
+                "optionValuesToUpdate".into(),
+                self.values_to_update.clone().into(),
+            );
+        }
+        if !self.values_to_delete.is_empty() {
+            vars.insert("optionValuesToDelete".into(), json!(self.values_to_delete));
+        }
+
+        vars
+    }
+}
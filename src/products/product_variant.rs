@@ -1,222 +1,749 @@
 #![allow(unused)]
 
-use std::{collections::HashMap, marker::PhantomData};
+use std::{
+    collections::{BTreeMap, HashMap},
+    marker::PhantomData,
+};
 
 use crate::{
-    common::{Id, Money, WeightUnit},
+    common::{resource, stream_connection, Edges, Id, Money, WeightUnit},
+    inventory::{InventoryItem, INVENTORY_ITEM_FIELDS},
+    metafields::{
+        deserialize_aliased_metafields, metafield_selection, metafields_connection, Metafield,
+        MetafieldInput,
+    },
     utils::{
-        run_query, ResponseTypes, ShopifyConfig, ShopifyConnection, ShopifyGqlError, ShopifyResult,
+        mutation_payload, run_request, GraphQlRequest, ResponseTypes, ShopifyClient,
+        ShopifyGqlError, ShopifyResult,
     },
 };
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 
 use super::{
+    bulk_connection_query, connection_query,
+    media::{Image, IMAGE_FIELDS},
     product::{Product, ProductQueryBuilder},
-    ProductsConnection,
+    product_option::SelectedOption,
+    ProductsConnection, CONNECTION_ARGUMENTS, CONNECTION_VARIABLES,
 };
 
-#[derive(Debug, Deserialize)]
+/// A variant of a product, such as a particular size or color.
+///
+/// Only the fields selected on the [`ProductVariantQueryBuilder`] are
+/// populated; the rest are `None`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct ProductVariant {
-    id: Id,
+pub struct ProductVariant {
+    id: Id<resource::ProductVariant>,
+    available_for_sale: Option<bool>,
+    barcode: Option<String>,
     compare_at_price: Option<Money>,
+    created_at: Option<DateTime<Utc>>,
+    display_name: Option<String>,
+    image: Option<Image>,
+    inventory_item: Option<InventoryItem>,
+    inventory_policy: Option<ProductVariantInventoryPolicy>,
     inventory_quantity: Option<i32>,
+    position: Option<i32>,
     price: Option<Money>,
     product: Option<Product>,
+    selected_options: Option<Vec<SelectedOption>>,
     sku: Option<String>,
+    tax_code: Option<String>,
+    taxable: Option<bool>,
     title: Option<String>,
-    weight: Option<f64>,
-    weight_unit: Option<WeightUnit>,
+    updated_at: Option<DateTime<Utc>>,
+
+    /// The inventory item's weight, selected under an alias so that it
+    /// doesn't clash with [`inventory_item`](Self::inventory_item).
+    weight_measurement: Option<WeightMeasurement>,
+
+    metafields: Option<Edges<Metafield>>,
+
+    /// The metafields selected by namespace and key, by their aliases.
+    #[serde(flatten, deserialize_with = "deserialize_aliased_metafields")]
+    aliased_metafields: BTreeMap<String, Option<Metafield>>,
 }
 
 impl ProductVariant {
-    pub(crate) fn id(&self) -> &Id {
+    pub fn id(&self) -> &Id<resource::ProductVariant> {
         &self.id
     }
 
-    pub(crate) fn compare_at_price(&self) -> Option<&Money> {
+    /// Returns `true` if the variant can be bought, either because it is in
+    /// stock or because it can be sold while out of stock.
+    pub fn available_for_sale(&self) -> Option<bool> {
+        self.available_for_sale
+    }
+
+    /// The variant's barcode, such as its UPC or ISBN.
+    pub fn barcode(&self) -> Option<&String> {
+        self.barcode.as_ref()
+    }
+
+    pub fn compare_at_price(&self) -> Option<&Money> {
         self.compare_at_price.as_ref()
     }
 
-    pub(crate) fn inventory_quantity(&self) -> Option<i32> {
+    pub fn created_at(&self) -> Option<&DateTime<Utc>> {
+        self.created_at.as_ref()
+    }
+
+    /// The product's title followed by the variant's, such as
+    /// `T-Shirt - Red`.
+    pub fn display_name(&self) -> Option<&String> {
+        self.display_name.as_ref()
+    }
+
+    pub fn image(&self) -> Option<&Image> {
+        self.image.as_ref()
+    }
+
+    pub fn inventory_item(&self) -> Option<&InventoryItem> {
+        self.inventory_item.as_ref()
+    }
+
+    pub fn inventory_policy(&self) -> Option<&ProductVariantInventoryPolicy> {
+        self.inventory_policy.as_ref()
+    }
+
+    pub fn inventory_quantity(&self) -> Option<i32> {
         self.inventory_quantity
     }
 
-    pub(crate) fn price(&self) -> Option<&Money> {
+    /// The variant's position among the product's variants, starting at 1.
+    pub fn position(&self) -> Option<i32> {
+        self.position
+    }
+
+    /// Returns the metafield with the given namespace and key, if it was
+    /// selected by [`ProductVariantQueryBuilder::metafield`] or is on the fetched page of
+    /// [`ProductVariantQueryBuilder::metafields`].
+    pub fn metafield(&self, namespace: &str, key: &str) -> Option<&Metafield> {
+        self.aliased_metafields
+            .values()
+            .flatten()
+            .chain(self.metafields.iter().flat_map(Edges::iter))
+            .find(|m| m.namespace() == namespace && m.key() == key)
+    }
+
+    pub fn metafields(&self) -> Option<&Edges<Metafield>> {
+        self.metafields.as_ref()
+    }
+
+    pub(crate) fn push_metafield(&mut self, metafield: Metafield) {
+        self.metafields
+            .get_or_insert_with(Edges::default)
+            .push(metafield);
+    }
+
+    pub fn price(&self) -> Option<&Money> {
         self.price.as_ref()
     }
 
-    pub(crate) fn product(&self) -> Option<&Product> {
+    pub fn product(&self) -> Option<&Product> {
         self.product.as_ref()
     }
 
-    pub(crate) fn sku(&self) -> Option<&String> {
+    /// The variant's value for each of the product's options, such as
+    /// `Color: Red` and `Size: Large`.
+    pub fn selected_options(&self) -> Option<&[SelectedOption]> {
+        self.selected_options.as_deref()
+    }
+
+    pub fn sku(&self) -> Option<&String> {
         self.sku.as_ref()
     }
 
-    pub(crate) fn title(&self) -> Option<&String> {
+    /// The Avalara tax code of the variant.
+    pub fn tax_code(&self) -> Option<&String> {
+        self.tax_code.as_ref()
+    }
+
+    pub fn taxable(&self) -> Option<bool> {
+        self.taxable
+    }
+
+    pub fn title(&self) -> Option<&String> {
         self.title.as_ref()
     }
 
-    pub(crate) fn weight(&self) -> Option<f64> {
-        self.weight
+    pub fn updated_at(&self) -> Option<&DateTime<Utc>> {
+        self.updated_at.as_ref()
+    }
+
+    pub fn weight(&self) -> Option<f64> {
+        self.weight_measurement.as_ref()?.weight().map(|w| w.value)
+    }
+
+    pub fn weight_unit(&self) -> Option<&WeightUnit> {
+        self.weight_measurement.as_ref()?.weight().map(|w| &w.unit)
+    }
+}
+
+/// The selection that fetches a variant's weight from its inventory item,
+/// read into [`WeightMeasurement`].
+const WEIGHT_FIELD: &str =
+    "weightMeasurement: inventoryItem { measurement { weight { value unit } } }";
+
+/// The `measurement` of a variant's inventory item, which holds its weight.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct WeightMeasurement {
+    measurement: Measurement,
+}
+
+impl WeightMeasurement {
+    fn weight(&self) -> Option<&Weight> {
+        self.measurement.weight.as_ref()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Measurement {
+    weight: Option<Weight>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Weight {
+    value: f64,
+    unit: WeightUnit,
+}
+
+/// Whether a variant can be sold once it is out of stock.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum ProductVariantInventoryPolicy {
+    /// Customers can't buy the variant once it is out of stock.
+    DENY,
+
+    /// Customers can keep buying the variant after it is out of stock.
+    CONTINUE,
+}
+
+/// The fields of a variant to create, such as the initial variants of
+/// [`ProductQueryBuilder::product_create`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(transparent)]
+pub struct VariantInput {
+    fields: Map<String, Value>,
+}
+
+impl VariantInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn price(mut self, price: Money) -> Self {
+        self.fields
+            .insert("price".into(), price.amount().to_string().into());
+        self
+    }
+
+    pub fn compare_at_price(mut self, compare_at_price: Money) -> Self {
+        self.fields.insert(
+            "compareAtPrice".into(),
+            compare_at_price.amount().to_string().into(),
+        );
+        self
+    }
+
+    pub fn sku(mut self, sku: &str) -> Self {
+        self.fields.insert("sku".into(), sku.into());
+        self
+    }
+
+    pub fn barcode(mut self, barcode: &str) -> Self {
+        self.fields.insert("barcode".into(), barcode.into());
+        self
+    }
+
+    pub fn inventory_policy(mut self, inventory_policy: ProductVariantInventoryPolicy) -> Self {
+        self.fields
+            .insert("inventoryPolicy".into(), json!(inventory_policy));
+        self
+    }
+
+    pub fn taxable(mut self, taxable: bool) -> Self {
+        self.fields.insert("taxable".into(), taxable.into());
+        self
+    }
+
+    pub fn tax_code(mut self, tax_code: &str) -> Self {
+        self.fields.insert("taxCode".into(), tax_code.into());
+        self
+    }
+
+    /// The variant's value for each of the product's options, by option name.
+    pub fn option_values(mut self, values: &[SelectedOption]) -> Self {
+        self.fields
+            .insert("optionValues".into(), option_values(values));
+        self
+    }
+
+    pub fn weight(mut self, weight: f64) -> Self {
+        self.fields.insert("weight".into(), weight.into());
+        self
+    }
+
+    pub fn weight_unit(mut self, weight_unit: WeightUnit) -> Self {
+        self.fields.insert("weightUnit".into(), json!(weight_unit));
+        self
+    }
+
+    pub fn metafields(mut self, metafields: Vec<MetafieldInput>) -> Self {
+        self.fields.insert("metafields".into(), json!(metafields));
+        self
     }
 
-    pub(crate) fn weight_unit(&self) -> Option<&WeightUnit> {
-        self.weight_unit.as_ref()
+    /// The variant as a `ProductVariantsBulkInput`.
+    pub(crate) fn bulk_input(&self) -> Value {
+        variant_input(&self.fields)
     }
 }
 
-/// All possible queries and mutations on a `Product`.
-#[derive(Debug, Deserialize, PartialEq)]
+/// Maps the fields of a variant, as the builders collect them, to a
+/// `ProductVariantInput` or `ProductVariantsBulkInput`, which take the SKU and
+/// weight under `inventoryItem` since API version 2024-04.
+fn variant_input(fields: &Map<String, Value>) -> Value {
+    let mut input = fields.clone();
+    let mut inventory_item = Map::new();
+
+    if let Some(sku) = input.remove("sku") {
+        inventory_item.insert("sku".into(), sku);
+    }
+
+    let mut weight = Map::new();
+    if let Some(value) = input.remove("weight") {
+        weight.insert("value".into(), value);
+    }
+    if let Some(unit) = input.remove("weightUnit") {
+        weight.insert("unit".into(), unit);
+    }
+    if !weight.is_empty() {
+        inventory_item.insert("measurement".into(), json!({ "weight": weight }));
+    }
+
+    if !inventory_item.is_empty() {
+        input.insert("inventoryItem".into(), inventory_item.into());
+    }
+
+    input.into()
+}
+
+fn option_values(values: &[SelectedOption]) -> Value {
+    values
+        .iter()
+        .map(|o| json!({ "optionName": o.name(), "name": o.value() }))
+        .collect()
+}
+
+/// All possible queries and mutations on a `ProductVariant`.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
-pub(crate) enum ProductVariantQueryType {
+#[allow(clippy::enum_variant_names)]
+pub enum ProductVariantQueryType {
     ProductVariant,
     ProductVariants(ProductsConnection),
-    ProductVariantUpdate(Id),
+    ProductVariantUpdate(Id<resource::ProductVariant>),
+    ProductVariantsBulkCreate(Id<resource::Product>),
+    ProductVariantsBulkUpdate(Id<resource::Product>),
+    ProductVariantsBulkDelete(Id<resource::Product>),
+    ProductVariantsBulkReorder(Id<resource::Product>),
 }
 
-#[derive(Debug, Deserialize)]
+/// Builds and runs a query or mutation that returns a [`ProductVariant`].
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct ProductVariantQueryBuilder {
-    id: Id,
+pub struct ProductVariantQueryBuilder {
+    id: Id<resource::ProductVariant>,
     fields: HashMap<String, PhantomData<u8>>,
-    inputs: Option<HashMap<String, PhantomData<u8>>>,
+    inputs: Option<Map<String, Value>>,
     query_type: ProductVariantQueryType,
+
+    /// Kept as a builder rather than rendered into `fields`, so that it can be
+    /// rendered for bulk operations too.
+    product: Option<Box<ProductQueryBuilder>>,
+
+    /// The connection and namespace of the selected `metafields`.
+    metafields: Option<(ProductsConnection, Option<String>)>,
 }
 
 impl ProductVariantQueryBuilder {
-    pub(crate) fn product_variant(id: Id) -> Self {
+    fn new(id: Id<resource::ProductVariant>, query_type: ProductVariantQueryType) -> Self {
         let mut fields = HashMap::new();
         fields.insert("id".into(), PhantomData);
 
-        let query_type = ProductVariantQueryType::ProductVariant;
-
         ProductVariantQueryBuilder {
             id,
             fields,
             inputs: None,
             query_type,
+            product: None,
+            metafields: None,
         }
     }
 
-    pub(crate) fn product_variants(conn: ProductsConnection) -> Self {
-        let mut fields = HashMap::new();
-        fields.insert("id".into(), PhantomData);
+    /// Fetches the variant with the given ID.
+    pub fn product_variant(id: Id<resource::ProductVariant>) -> Self {
+        Self::new(id, ProductVariantQueryType::ProductVariant)
+    }
 
-        let query_type = ProductVariantQueryType::ProductVariants(conn);
-        let id = Id::default();
+    /// Fetches a page of the store's variants.
+    ///
+    /// Run it with [`build_connection`](Self::build_connection) or
+    /// [`stream`](Self::stream), or pass it to [`ProductQueryBuilder::variants`]
+    /// to select a product's variants instead.
+    pub fn product_variants(conn: ProductsConnection) -> Self {
+        Self::new(
+            Id::default(),
+            ProductVariantQueryType::ProductVariants(conn),
+        )
+    }
+
+    /// Updates the variant with the given ID.
+    ///
+    ///**NOTE:** Only call the `update_` methods on the returned builder.
+    pub fn product_variant_update(id: Id<resource::ProductVariant>) -> Self {
+        let mut inputs = Map::new();
+        inputs.insert("id".into(), id.inner().clone().into());
+
+        let query_type = ProductVariantQueryType::ProductVariantUpdate(id.clone());
 
         ProductVariantQueryBuilder {
-            id,
-            fields,
-            inputs: None,
-            query_type,
+            inputs: Some(inputs),
+            ..Self::new(id, query_type)
         }
     }
 
-    ///**NOTE:** Only call the `update_` methods on the returned builder.
-    pub(crate) fn product_variant_update(id: Id) -> Self {
-        let mut fields = HashMap::new();
-        fields.insert("id".into(), PhantomData);
+    /// Creates variants of the product with the given ID in one request.
+    ///
+    /// A variant's weight is only accepted along with its unit. Run it with
+    /// [`build_many`](Self::build_many), which returns the new variants with
+    /// the fields selected on this builder.
+    pub fn product_variants_bulk_create(
+        product_id: Id<resource::Product>,
+        variants: Vec<VariantInput>,
+    ) -> Self {
+        let inputs: Vec<_> = variants.iter().map(VariantInput::bulk_input).collect();
 
-        let mut inputs = Some(HashMap::new());
-        inputs
-            .as_mut()
-            .unwrap()
-            .insert(format!("id: \"{}\"", id.inner()), PhantomData);
+        Self::bulk_mutation(
+            ProductVariantQueryType::ProductVariantsBulkCreate(product_id),
+            json!({ "variants": inputs }),
+        )
+    }
 
-        let query_type = ProductVariantQueryType::ProductVariantUpdate(id.clone());
+    /// Updates variants of the product with the given ID in one request.
+    ///
+    /// Each of `variants` is created with
+    /// [`product_variant_update`](Self::product_variant_update) and its
+    /// `update_` methods; only their inputs are used, and a weight is only
+    /// accepted along with its unit. Run it with
+    /// [`build_many`](Self::build_many), which returns the updated variants
+    /// with the fields selected on this builder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of `variants` was not created with
+    /// [`product_variant_update`](Self::product_variant_update), as it has no
+    /// variant ID to update.
+    pub fn product_variants_bulk_update(
+        product_id: Id<resource::Product>,
+        variants: Vec<ProductVariantQueryBuilder>,
+    ) -> Self {
+        let inputs: Vec<_> = variants
+            .iter()
+            .map(|v| match (&v.query_type, &v.inputs) {
+                (ProductVariantQueryType::ProductVariantUpdate(_), Some(inputs)) => {
+                    variant_input(inputs)
+                }
+                _ => panic!(
+                    "`product_variants_bulk_update` requires `ProductVariantQueryBuilder::product_variant_update` builders"
+                ),
+            })
+            .collect();
+
+        Self::bulk_mutation(
+            ProductVariantQueryType::ProductVariantsBulkUpdate(product_id),
+            json!({ "variants": inputs }),
+        )
+    }
+
+    /// Deletes variants of the product with the given ID in one request.
+    ///
+    /// [`build_many`](Self::build_many) returns the deleted variants with only
+    /// their `id` set.
+    pub fn product_variants_bulk_delete(
+        product_id: Id<resource::Product>,
+        variant_ids: &[Id<resource::ProductVariant>],
+    ) -> Self {
+        Self::bulk_mutation(
+            ProductVariantQueryType::ProductVariantsBulkDelete(product_id),
+            json!({ "variantsIds": variant_ids }),
+        )
+    }
+
+    /// Moves variants of the product with the given ID to new positions,
+    /// starting at 1.
+    ///
+    /// The mutation doesn't return the variants, so
+    /// [`build_many`](Self::build_many) returns the moved variants with only
+    /// their `id` and new `position` set.
+    pub fn product_variants_bulk_reorder(
+        product_id: Id<resource::Product>,
+        positions: &[(Id<resource::ProductVariant>, u32)],
+    ) -> Self {
+        let positions: Vec<_> = positions
+            .iter()
+            .map(|(id, position)| json!({ "id": id, "position": position }))
+            .collect();
+
+        Self::bulk_mutation(
+            ProductVariantQueryType::ProductVariantsBulkReorder(product_id),
+            json!({ "positions": positions }),
+        )
+    }
+
+    /// Starts a `productVariantsBulk*` mutation, taking the product's ID from
+    /// `query_type` and adding it to `variables` as `productId`.
+    fn bulk_mutation(query_type: ProductVariantQueryType, variables: Value) -> Self {
+        let product_id = match &query_type {
+            ProductVariantQueryType::ProductVariantsBulkCreate(id)
+            | ProductVariantQueryType::ProductVariantsBulkUpdate(id)
+            | ProductVariantQueryType::ProductVariantsBulkDelete(id)
+            | ProductVariantQueryType::ProductVariantsBulkReorder(id) => id.clone(),
+
+            _ => unreachable!(),
+        };
+
+        let mut inputs = match variables {
+            Value::Object(vars) => vars,
+            _ => unreachable!(),
+        };
+        inputs.insert("productId".into(), product_id.inner().clone().into());
 
         ProductVariantQueryBuilder {
-            id,
-            fields,
-            inputs,
-            query_type,
+            inputs: Some(inputs),
+            ..Self::new(Id::default(), query_type)
         }
     }
 
-    pub(crate) fn compare_at_price(mut self) -> Self {
+    pub fn available_for_sale(mut self) -> Self {
+        self.fields.insert("availableForSale".into(), PhantomData);
+        self
+    }
+
+    pub fn barcode(mut self) -> Self {
+        self.fields.insert("barcode".into(), PhantomData);
+        self
+    }
+
+    pub fn update_barcode(mut self, barcode: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("barcode".into(), barcode.into());
+        self
+    }
+
+    pub fn compare_at_price(mut self) -> Self {
         self.fields.insert("compareAtPrice".into(), PhantomData);
         self
     }
 
-    pub(crate) fn update_compare_at_price(mut self, compare_at_price: Money) -> Self {
-        let compare_at_price = format!("compareAtPrice: {}", compare_at_price.0.to_string());
+    pub fn update_compare_at_price(mut self, compare_at_price: Money) -> Self {
+        self.inputs.as_mut().unwrap().insert(
+            "compareAtPrice".into(),
+            compare_at_price.amount().to_string().into(),
+        );
+        self
+    }
+
+    /// Selects the metafield with the given namespace and key, which
+    /// [`ProductVariant::metafield`] then returns.
+    pub fn metafield(mut self, namespace: &str, key: &str) -> Self {
+        self.fields
+            .insert(metafield_selection(namespace, key), PhantomData);
+        self
+    }
+
+    /// Selects a page of the variant's metafields, optionally only the ones in
+    /// `namespace`.
+    ///
+    /// In bulk operations, every metafield is fetched and `conn` is ignored.
+    pub fn metafields(mut self, conn: ProductsConnection, namespace: Option<&str>) -> Self {
+        self.metafields = Some((conn, namespace.map(String::from)));
+        self
+    }
 
+    /// Creates or updates metafields of the variant.
+    pub fn update_metafields(mut self, metafields: Vec<MetafieldInput>) -> Self {
         self.inputs
             .as_mut()
             .unwrap()
-            .insert(compare_at_price, PhantomData);
+            .insert("metafields".into(), json!(metafields));
+        self
+    }
+
+    pub fn created_at(mut self) -> Self {
+        self.fields.insert("createdAt".into(), PhantomData);
+        self
+    }
+
+    pub fn display_name(mut self) -> Self {
+        self.fields.insert("displayName".into(), PhantomData);
+        self
+    }
+
+    pub fn image(mut self) -> Self {
+        self.fields
+            .insert(format!("image {{ {} }}", IMAGE_FIELDS), PhantomData);
+        self
+    }
+
+    pub fn inventory_item(mut self) -> Self {
+        self.fields
+            .insert(INVENTORY_ITEM_FIELDS.into(), PhantomData);
+        self
+    }
+
+    pub fn inventory_policy(mut self) -> Self {
+        self.fields.insert("inventoryPolicy".into(), PhantomData);
         self
     }
 
-    pub(crate) fn inventory_quantity(mut self) -> Self {
+    pub fn update_inventory_policy(
+        mut self,
+        inventory_policy: ProductVariantInventoryPolicy,
+    ) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("inventoryPolicy".into(), json!(inventory_policy));
+        self
+    }
+
+    pub fn inventory_quantity(mut self) -> Self {
         self.fields.insert("inventoryQuantity".into(), PhantomData);
         self
     }
 
-    pub(crate) fn price(mut self) -> Self {
-        self.fields.insert("price".into(), PhantomData);
+    pub fn position(mut self) -> Self {
+        self.fields.insert("position".into(), PhantomData);
         self
     }
 
-    pub(crate) fn update_price(mut self, price: Money) -> Self {
-        let price = format!("price: {}", price.0.to_string());
+    pub fn price(mut self) -> Self {
+        self.fields.insert("price".into(), PhantomData);
+        self
+    }
 
-        self.inputs.as_mut().unwrap().insert(price, PhantomData);
+    pub fn update_price(mut self, price: Money) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("price".into(), price.amount().to_string().into());
         self
     }
 
     /// NOTE: Calling `.variants()` on the `product_query` will cause an infinte cycle.
-    pub(crate) fn product(mut self, product_query: ProductQueryBuilder) -> Self {
-        let prod_str = format!("product {{ {} }}", product_query.fields().join("\n,"));
+    pub fn product(mut self, product_query: ProductQueryBuilder) -> Self {
+        self.product = Some(Box::new(product_query));
+        self
+    }
 
-        self.fields.insert(prod_str, PhantomData);
+    /// Sets the variant's value for each of the product's options, by option
+    /// name.
+    pub fn update_option_values(mut self, values: &[SelectedOption]) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("optionValues".into(), option_values(values));
         self
     }
 
-    pub(crate) fn sku(mut self) -> Self {
+    pub fn selected_options(mut self) -> Self {
+        self.fields
+            .insert("selectedOptions { name value }".into(), PhantomData);
+        self
+    }
+
+    pub fn sku(mut self) -> Self {
         self.fields.insert("sku".into(), PhantomData);
         self
     }
 
-    pub(crate) fn update_sku(mut self, sku: &str) -> Self {
-        let sku = format!("sku: \"{}\"", sku);
+    pub fn update_sku(mut self, sku: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("sku".into(), sku.into());
+        self
+    }
 
-        self.inputs.as_mut().unwrap().insert(sku, PhantomData);
+    pub fn tax_code(mut self) -> Self {
+        self.fields.insert("taxCode".into(), PhantomData);
         self
     }
 
-    pub(crate) fn title(mut self) -> Self {
-        self.fields.insert("title".into(), PhantomData);
+    pub fn update_tax_code(mut self, tax_code: &str) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("taxCode".into(), tax_code.into());
+        self
+    }
+
+    pub fn taxable(mut self) -> Self {
+        self.fields.insert("taxable".into(), PhantomData);
+        self
+    }
+
+    pub fn update_taxable(mut self, taxable: bool) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("taxable".into(), taxable.into());
         self
     }
 
-    pub(crate) fn weight(mut self) -> Self {
-        self.fields.insert("weight".into(), PhantomData);
+    pub fn title(mut self) -> Self {
+        self.fields.insert("title".into(), PhantomData);
         self
     }
 
-    pub(crate) fn update_weight(mut self, weight: f64) -> Self {
-        let weight = format!("weight: {}", weight);
+    pub fn updated_at(mut self) -> Self {
+        self.fields.insert("updatedAt".into(), PhantomData);
+        self
+    }
 
-        self.inputs.as_mut().unwrap().insert(weight, PhantomData);
+    /// Selects the weight of the variant's inventory item.
+    pub fn weight(mut self) -> Self {
+        self.fields.insert(WEIGHT_FIELD.into(), PhantomData);
         self
     }
 
-    pub(crate) fn weight_unit(mut self) -> Self {
-        self.fields.insert("weightUnit".into(), PhantomData);
+    pub fn update_weight(mut self, weight: f64) -> Self {
+        self.inputs
+            .as_mut()
+            .unwrap()
+            .insert("weight".into(), weight.into());
         self
     }
 
-    pub(crate) fn update_weight_unit(mut self, weight_unit: WeightUnit) -> Self {
-        let weight_unit = format!("weightUnit: {:?}", weight_unit);
+    /// Selects the unit of the weight of the variant's inventory item.
+    pub fn weight_unit(mut self) -> Self {
+        self.fields.insert(WEIGHT_FIELD.into(), PhantomData);
+        self
+    }
 
+    pub fn update_weight_unit(mut self, weight_unit: WeightUnit) -> Self {
         self.inputs
             .as_mut()
             .unwrap()
-            .insert(weight_unit, PhantomData);
+            .insert("weightUnit".into(), format!("{:?}", weight_unit).into());
         self
     }
 
@@ -224,61 +751,432 @@ impl ProductVariantQueryBuilder {
         self.fields.keys().map(|v| v.as_str()).collect()
     }
 
-    pub(crate) fn inputs(&self) -> Option<Vec<&str>> {
-        self.inputs
-            .as_ref()
-            .map_or(None, |m| Some(m.keys().map(|v| v.as_str()).collect()))
+    pub(crate) fn inputs(&self) -> Option<&Map<String, Value>> {
+        self.inputs.as_ref()
     }
 
     pub(crate) fn query_type(&self) -> &ProductVariantQueryType {
         &self.query_type
     }
 
-    pub(crate) async fn build(self, config: ShopifyConfig) -> ShopifyResult<ProductVariant> {
-        let fields = self.fields().join("\n,");
+    /// Renders the selected fields, including the nested product.
+    ///
+    /// With `bulk` set, nested connections are rendered without pagination
+    /// arguments or `pageInfo`, as bulk operations require.
+    pub(crate) fn selection(&self, bulk: bool) -> String {
+        let mut fields: Vec<String> = self.fields().into_iter().map(String::from).collect();
+
+        if let Some(product) = &self.product {
+            fields.push(format!("product {{ {} }}", product.selection(bulk)));
+        }
+
+        if let Some((conn, namespace)) = &self.metafields {
+            let conn = if bulk { None } else { Some(conn) };
+            fields.push(metafields_connection(conn, namespace.as_deref()));
+        }
+
+        fields.join("\n,")
+    }
+
+    /// Renders a [`product_variants`](Self::product_variants) query as a bulk
+    /// operation query, which walks the whole connection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the builder was not created with
+    /// [`product_variants`](Self::product_variants).
+    pub(crate) fn bulk_query(&self) -> String {
+        match &self.query_type {
+            ProductVariantQueryType::ProductVariants(_) => format!(
+                "{{ {} }}",
+                bulk_connection_query("productVariants", "", &self.selection(true))
+            ),
+
+            _ => panic!(
+                "bulk operations require a `ProductVariantQueryBuilder::product_variants` query"
+            ),
+        }
+    }
+
+    fn request(&self) -> GraphQlRequest {
+        let fields = self.selection(false);
+
+        match &self.query_type {
+            ProductVariantQueryType::ProductVariant => GraphQlRequest::new(format!(
+                "query productVariant($id: ID!) {{ productVariant(id: $id) {{ {} }} }}",
+                fields
+            ))
+            .with_variable("id", self.id.inner().clone()),
 
-        let query = match &self.query_type {
-            ProductVariantQueryType::ProductVariant => {
-                format!(
-                    "query {{ productVariant(id: \"{}\") {{ {} }} }}",
-                    self.id.inner(),
-                    fields
-                )
+            ProductVariantQueryType::ProductVariants(conn) => GraphQlRequest::new(format!(
+                "query productVariants({}) {{ {} }}",
+                CONNECTION_VARIABLES,
+                connection_query("productVariants", CONNECTION_ARGUMENTS, &fields)
+            ))
+            .with_variables(conn.variables()),
+
+            ProductVariantQueryType::ProductVariantUpdate(_) => {
+                GraphQlRequest::new(self.update_mutation()).with_variable("input", self.update_input())
             }
 
-            ProductVariantQueryType::ProductVariants(conn_type) => match conn_type {
-                ProductsConnection::First(n) => {
-                    format!(
-                        "query {{ productVariant(first: {}) {{ edges {{ node {{ {} }} }} }}  }}",
-                        n, fields
-                    )
-                }
+            ProductVariantQueryType::ProductVariantsBulkCreate(_) => GraphQlRequest::new(format!(
+                "mutation productVariantsBulkCreate($productId: ID!, $variants: [ProductVariantsBulkInput!]!) {{ productVariantsBulkCreate(productId: $productId, variants: $variants) {{ productVariants {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs().cloned().unwrap_or_default()),
 
-                ProductsConnection::Last(n) => {
-                    format!(
-                        "query {{ productVariant(last: {}) {{ edges {{ node {{ {} }} }} }}  }}",
-                        n, fields
-                    )
-                }
-            },
+            ProductVariantQueryType::ProductVariantsBulkUpdate(_) => GraphQlRequest::new(format!(
+                "mutation productVariantsBulkUpdate($productId: ID!, $variants: [ProductVariantsBulkInput!]!) {{ productVariantsBulkUpdate(productId: $productId, variants: $variants) {{ productVariants {{ {} }} userErrors {{ field message code }} }} }}",
+                fields
+            ))
+            .with_variables(self.inputs().cloned().unwrap_or_default()),
+
+            ProductVariantQueryType::ProductVariantsBulkDelete(_) => GraphQlRequest::new(
+                "mutation productVariantsBulkDelete($productId: ID!, $variantsIds: [ID!]!) { productVariantsBulkDelete(productId: $productId, variantsIds: $variantsIds) { product { id } userErrors { field message code } } }",
+            )
+            .with_variables(self.inputs().cloned().unwrap_or_default()),
+
+            ProductVariantQueryType::ProductVariantsBulkReorder(_) => GraphQlRequest::new(
+                "mutation productVariantsBulkReorder($productId: ID!, $positions: [ProductVariantPositionInput!]!) { productVariantsBulkReorder(productId: $productId, positions: $positions) { product { id } userErrors { field message code } } }",
+            )
+            .with_variables(self.inputs().cloned().unwrap_or_default()),
+        }
+    }
+
+    /// Renders a [`product_variant_update`](Self::product_variant_update) as a
+    /// mutation that takes its input as the `$input` variable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the builder was not created with
+    /// [`product_variant_update`](Self::product_variant_update).
+    pub(crate) fn update_mutation(&self) -> String {
+        match &self.query_type {
+            ProductVariantQueryType::ProductVariantUpdate(_) => format!(
+                "mutation productVariantUpdate($input: ProductVariantInput!) {{ productVariantUpdate(input: $input) {{ productVariant {{ {} }} userErrors {{ field message }} }} }}",
+                self.selection(false)
+            ),
+
+            _ => panic!(
+                "expected a `ProductVariantQueryBuilder::product_variant_update` mutation"
+            ),
+        }
+    }
 
-            // TODO: Handle user errors
-            ProductVariantQueryType::ProductVariantUpdate(id) => {
-                format!(
-                    "mutation {{ productVariantUpdate(input: {{ {} }}) {{ productVariant {{ {} }} }}  }}",
-                    self.inputs().unwrap().join("\n,"),
-                    fields
-                )
+    /// The variables of an [`update_mutation`](Self::update_mutation).
+    pub(crate) fn update_variables(&self) -> Value {
+        json!({ "input": self.update_input() })
+    }
+
+    /// The `ProductVariantInput` of a
+    /// [`product_variant_update`](Self::product_variant_update).
+    fn update_input(&self) -> Value {
+        variant_input(self.inputs().unwrap_or(&Map::new()))
+    }
+
+    /// Sends the query through `client` and returns the variant.
+    pub async fn build(self, client: &ShopifyClient) -> ShopifyResult<ProductVariant> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::ProductVariant(v) => Ok(v),
+
+            ResponseTypes::ProductVariantUpdate {
+                product_variant,
+                user_errors,
+            } => mutation_payload(product_variant, user_errors),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Sends one of the bulk mutations, such as
+    /// [`product_variants_bulk_update`](Self::product_variants_bulk_update),
+    /// through `client` and returns the affected variants.
+    pub async fn build_many(self, client: &ShopifyClient) -> ShopifyResult<Vec<ProductVariant>> {
+        let deleted = match (&self.query_type, self.inputs()) {
+            (ProductVariantQueryType::ProductVariantsBulkDelete(_), Some(inputs)) => {
+                serde_json::from_value(inputs["variantsIds"].clone())
+                    .map_err(|e| ShopifyGqlError::ResponseError(e.to_string()))?
             }
+
+            _ => vec![],
         };
 
-        let res = run_query(config, query).await?;
-        match res.data {
-            ResponseTypes::ProductVariant(v) => Ok(v),
+        // Each `{ id, position }` of a reorder is a variant with only those
+        // fields set.
+        let moved: Vec<ProductVariant> = match (&self.query_type, self.inputs()) {
+            (ProductVariantQueryType::ProductVariantsBulkReorder(_), Some(inputs)) => {
+                serde_json::from_value(inputs["positions"].clone())
+                    .map_err(|e| ShopifyGqlError::ResponseError(e.to_string()))?
+            }
 
-            ResponseTypes::ProductVariantUpdate { product_variant } => Ok(product_variant),
+            _ => vec![],
+        };
+
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::ProductVariantsBulkCreate {
+                product_variants,
+                user_errors,
+                ..
+            }
+            | ResponseTypes::ProductVariantsBulkUpdate {
+                product_variants,
+                user_errors,
+            } => mutation_payload(product_variants, user_errors),
+
+            ResponseTypes::ProductVariantsBulkDelete {
+                product,
+                user_errors,
+            } => {
+                mutation_payload(product, user_errors)?;
+
+                Ok(deleted
+                    .into_iter()
+                    .map(|id| ProductVariant {
+                        id,
+                        ..ProductVariant::default()
+                    })
+                    .collect())
+            }
+
+            ResponseTypes::ProductVariantsBulkReorder {
+                product,
+                user_errors,
+            } => {
+                mutation_payload(product, user_errors)?;
+
+                Ok(moved)
+            }
 
             _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
         }
     }
+
+    /// Sends a [`product_variants`](Self::product_variants) query through
+    /// `client` and returns the page of variants.
+    pub async fn build_connection(
+        self,
+        client: &ShopifyClient,
+    ) -> ShopifyResult<Edges<ProductVariant>> {
+        let res = run_request(client, &self.request()).await?.into_data()?;
+        match res {
+            ResponseTypes::ProductVariants(v) => Ok(v),
+
+            _ => Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+        }
+    }
+
+    /// Runs a [`product_variants`](Self::product_variants) query page by page,
+    /// yielding every variant until the connection is exhausted.
+    ///
+    /// The connection passed to `product_variants` sets the page size and
+    /// where to start. Yields a single [`ShopifyGqlError::InvalidQuery`] if
+    /// the builder was not created with
+    /// [`product_variants`](Self::product_variants).
+    pub fn stream(
+        self,
+        client: &ShopifyClient,
+    ) -> impl Stream<Item = ShopifyResult<ProductVariant>> {
+        let first = match &self.query_type {
+            ProductVariantQueryType::ProductVariants(conn) => Ok(conn.clone()),
+            _ => Err(ShopifyGqlError::InvalidQuery(
+                "`stream` requires a `ProductVariantQueryBuilder::product_variants` query".into(),
+            )),
+        };
+
+        stream_connection(
+            self,
+            first,
+            client,
+            move |page, conn| page.query_type = ProductVariantQueryType::ProductVariants(conn),
+            |page, client| async move { page.build_connection(&client).await },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_variant_fields() {
+        let variant: ProductVariant = serde_json::from_value(json!({
+            "id": "gid://shopify/ProductVariant/1",
+            "availableForSale": true,
+            "barcode": "0123456789012",
+            "createdAt": "2024-01-02T03:04:05Z",
+            "displayName": "T-Shirt - Red",
+            "image": {
+                "id": "gid://shopify/ProductImage/5",
+                "url": "https://cdn.shopify.com/red.png",
+                "altText": null,
+                "width": 800,
+                "height": 600
+            },
+            "inventoryItem": {
+                "id": "gid://shopify/InventoryItem/7",
+                "sku": "red",
+                "tracked": true,
+                "requiresShipping": true
+            },
+            "inventoryPolicy": "CONTINUE",
+            "position": 2,
+            "taxCode": null,
+            "taxable": false
+        }))
+        .unwrap();
+
+        assert_eq!(variant.available_for_sale(), Some(true));
+        assert_eq!(variant.display_name(), Some(&"T-Shirt - Red".into()));
+        assert_eq!(variant.image().unwrap().width(), Some(800));
+        assert_eq!(
+            variant.inventory_item().unwrap().id(),
+            &Id::<resource::InventoryItem>::from_legacy_id("7").unwrap()
+        );
+        assert_eq!(
+            variant.inventory_policy(),
+            Some(&ProductVariantInventoryPolicy::CONTINUE)
+        );
+        assert_eq!(variant.position(), Some(2));
+        assert_eq!(variant.tax_code(), None);
+        assert_eq!(variant.taxable(), Some(false));
+    }
+
+    #[test]
+    fn ignores_unknown_keys_next_to_aliased_metafields() {
+        let variant: ProductVariant = serde_json::from_value(json!({
+            "__typename": "ProductVariant",
+            "id": "gid://shopify/ProductVariant/1",
+            "metafield_637573746f6d_636f6c6f72": {
+                "id": "gid://shopify/Metafield/3",
+                "namespace": "custom",
+                "key": "color",
+                "type": "color",
+                "value": "#ff0000"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            variant.metafield("custom", "color").unwrap().value(),
+            "#ff0000"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "requires `ProductVariantQueryBuilder::product_variant_update`")]
+    fn rejects_bulk_updates_without_a_variant_id() {
+        ProductVariantQueryBuilder::product_variants_bulk_update(
+            Id::product("10").unwrap(),
+            vec![ProductVariantQueryBuilder::product_variants(ProductsConnection::First(1)).sku()],
+        );
+    }
+
+    #[test]
+    fn sends_bulk_updates_in_one_request() {
+        let variants = ["1", "2"]
+            .map(|id| {
+                ProductVariantQueryBuilder::product_variant_update(Id::product_variant(id).unwrap())
+                    .update_sku(&format!("sku-{}", id))
+                    .update_price("9.99".parse().unwrap())
+            })
+            .to_vec();
+
+        let request = ProductVariantQueryBuilder::product_variants_bulk_update(
+            Id::product("10").unwrap(),
+            variants,
+        )
+        .sku()
+        .request();
+
+        assert!(request.query().contains("productVariantsBulkUpdate("));
+        assert!(request.query().contains("sku"));
+        assert_eq!(
+            Value::Object(request.variables().clone()),
+            json!({
+                "productId": "gid://shopify/Product/10",
+                "variants": [
+                    {
+                        "id": "gid://shopify/ProductVariant/1",
+                        "price": "9.99",
+                        "inventoryItem": { "sku": "sku-1" }
+                    },
+                    {
+                        "id": "gid://shopify/ProductVariant/2",
+                        "price": "9.99",
+                        "inventoryItem": { "sku": "sku-2" }
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn moves_sku_and_weight_under_the_inventory_item() {
+        let update =
+            ProductVariantQueryBuilder::product_variant_update(Id::product_variant("1").unwrap())
+                .update_sku("red-xl")
+                .update_weight(1.5)
+                .update_weight_unit(WeightUnit::KILOGRAMS)
+                .update_barcode("0123");
+
+        let expected = json!({
+            "id": "gid://shopify/ProductVariant/1",
+            "barcode": "0123",
+            "inventoryItem": {
+                "sku": "red-xl",
+                "measurement": { "weight": { "value": 1.5, "unit": "KILOGRAMS" } }
+            }
+        });
+        assert_eq!(update.request().variables()["input"], expected);
+
+        let request = ProductVariantQueryBuilder::product_variants_bulk_update(
+            Id::product("10").unwrap(),
+            vec![update],
+        )
+        .request();
+        assert_eq!(request.variables()["variants"], json!([expected]));
+
+        let request = ProductVariantQueryBuilder::product_variants_bulk_create(
+            Id::product("10").unwrap(),
+            vec![VariantInput::new().sku("blue-s").taxable(true)],
+        )
+        .request();
+        assert_eq!(
+            request.variables()["variants"],
+            json!([{ "taxable": true, "inventoryItem": { "sku": "blue-s" } }])
+        );
+    }
+
+    #[test]
+    fn reorders_without_reselecting_variants() {
+        let request = ProductVariantQueryBuilder::product_variants_bulk_reorder(
+            Id::product("10").unwrap(),
+            &[(Id::product_variant("2").unwrap(), 1)],
+        )
+        .sku()
+        .request();
+
+        assert!(!request.query().contains("variants("));
+
+        let moved: Vec<ProductVariant> =
+            serde_json::from_value(request.variables()["positions"].clone()).unwrap();
+        assert_eq!(moved[0].id(), &Id::product_variant("2").unwrap());
+        assert_eq!(moved[0].position(), Some(1));
+    }
+
+    #[test]
+    fn passes_option_values_by_name() {
+        let request = ProductVariantQueryBuilder::product_variants_bulk_create(
+            Id::product("10").unwrap(),
+            vec![VariantInput::new().option_values(&[SelectedOption::new("Size", "XL")])],
+        )
+        .request();
+
+        assert_eq!(
+            request.variables()["variants"],
+            json!([{ "optionValues": [{ "optionName": "Size", "name": "XL" }] }])
+        );
+    }
 }
@@ -0,0 +1,324 @@
+use serde::Deserialize;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The cost of a query, as reported in `extensions.cost`.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryCost {
+    requested_query_cost: f64,
+
+    /// `None` if the query was throttled and never ran.
+    actual_query_cost: Option<f64>,
+
+    throttle_status: ThrottleStatus,
+}
+
+impl QueryCost {
+    pub fn requested_query_cost(&self) -> f64 {
+        self.requested_query_cost
+    }
+
+    pub fn actual_query_cost(&self) -> Option<f64> {
+        self.actual_query_cost
+    }
+
+    pub fn throttle_status(&self) -> &ThrottleStatus {
+        &self.throttle_status
+    }
+}
+
+/// The state of the store's cost bucket after a query.
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottleStatus {
+    maximum_available: f64,
+    currently_available: f64,
+    restore_rate: f64,
+}
+
+impl ThrottleStatus {
+    /// The size of the bucket.
+    pub fn maximum_available(&self) -> f64 {
+        self.maximum_available
+    }
+
+    pub fn currently_available(&self) -> f64 {
+        self.currently_available
+    }
+
+    /// The number of points restored to the bucket every second.
+    pub fn restore_rate(&self) -> f64 {
+        self.restore_rate
+    }
+}
+
+/// Controls how a [`ShopifyClient`](crate::utils::ShopifyClient) paces its
+/// requests.
+#[derive(Debug, Clone)]
+pub struct RateLimitOptions {
+    /// The cost reserved for a query that hasn't been sent before. Once a
+    /// query has been sent, the `requestedQueryCost` Shopify reported for it is
+    /// reserved instead.
+    pub default_query_cost: f64,
+
+    /// How many times a `THROTTLED` query is retried before giving up.
+    pub max_retries: u32,
+
+    /// The delay before the first retry; it doubles on every retry after that.
+    pub min_backoff: Duration,
+
+    /// The longest delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RateLimitOptions {
+    fn default() -> Self {
+        Self {
+            default_query_cost: 10.0,
+            max_retries: 5,
+            min_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// The points reserved for a request that is in flight.
+#[derive(Debug)]
+pub(crate) struct Reservation {
+    key: u64,
+    cost: f64,
+}
+
+/// A leaky bucket that mirrors the store's `throttleStatus`, shared by every
+/// clone of a client.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    options: RateLimitOptions,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(options: RateLimitOptions) -> Self {
+        Self {
+            options,
+            bucket: Mutex::new(Bucket::new(Instant::now())),
+        }
+    }
+
+    pub(crate) fn options(&self) -> &RateLimitOptions {
+        &self.options
+    }
+
+    /// Waits until there are enough points in the bucket for `query` and
+    /// reserves them.
+    pub(crate) async fn acquire(&self, query: &str) -> Reservation {
+        let key = {
+            let mut hasher = DefaultHasher::new();
+            query.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let cost = bucket
+                    .costs
+                    .get(&key)
+                    .copied()
+                    .unwrap_or(self.options.default_query_cost);
+
+                match bucket.try_reserve(cost, Instant::now()) {
+                    Ok(cost) => return Reservation { key, cost },
+                    Err(wait) => wait,
+                }
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Releases a reservation and syncs the bucket with the cost Shopify
+    /// reported, if there was one.
+    pub(crate) fn complete(&self, reservation: Reservation, cost: Option<&QueryCost>) {
+        let mut bucket = self.bucket.lock().unwrap();
+
+        match cost {
+            Some(cost) => {
+                bucket.sync(reservation.cost, &cost.throttle_status, Instant::now());
+                bucket.remember(reservation.key, cost.requested_query_cost);
+            }
+
+            // The request never reached Shopify, so give the points back.
+            None => bucket.refund(reservation.cost),
+        }
+    }
+
+    /// How long to wait before retrying a throttled query.
+    pub(crate) fn backoff(&self, attempt: u32, cost: Option<&QueryCost>) -> Duration {
+        let exponential = self
+            .options
+            .min_backoff
+            .saturating_mul(2u32.saturating_pow(attempt));
+
+        let restore = cost.map_or(Duration::ZERO, |cost| {
+            let status = &cost.throttle_status;
+            let missing = cost.requested_query_cost - status.currently_available;
+
+            if missing > 0.0 && status.restore_rate > 0.0 {
+                Duration::from_secs_f64(missing / status.restore_rate)
+            } else {
+                Duration::ZERO
+            }
+        });
+
+        exponential.max(restore).min(self.options.max_backoff)
+    }
+}
+
+/// Stop remembering query costs past this many distinct queries.
+const MAX_REMEMBERED_COSTS: usize = 1024;
+
+#[derive(Debug)]
+struct Bucket {
+    maximum_available: f64,
+    currently_available: f64,
+    restore_rate: f64,
+    updated_at: Instant,
+
+    /// Points reserved by requests that haven't completed yet.
+    in_flight: f64,
+
+    /// The last `requestedQueryCost` of each query, keyed by its hash.
+    costs: HashMap<u64, f64>,
+}
+
+impl Bucket {
+    /// Starts with the limits of a standard plan until Shopify reports the
+    /// real ones.
+    fn new(now: Instant) -> Self {
+        Self {
+            maximum_available: 1000.0,
+            currently_available: 1000.0,
+            restore_rate: 50.0,
+            updated_at: now,
+            in_flight: 0.0,
+            costs: HashMap::new(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+
+        self.currently_available =
+            (self.currently_available + elapsed * self.restore_rate).min(self.maximum_available);
+        self.updated_at = now;
+    }
+
+    /// Reserves `cost` points, returning how many were reserved, or how long
+    /// to wait until they are available.
+    fn try_reserve(&mut self, cost: f64, now: Instant) -> Result<f64, Duration> {
+        self.refill(now);
+
+        // A query can never cost more than the whole bucket.
+        let cost = cost.min(self.maximum_available);
+
+        if self.currently_available >= cost {
+            self.currently_available -= cost;
+            self.in_flight += cost;
+            Ok(cost)
+        } else {
+            let missing = cost - self.currently_available;
+            Err(Duration::from_secs_f64(missing / self.restore_rate))
+        }
+    }
+
+    fn refund(&mut self, reserved: f64) {
+        self.in_flight = (self.in_flight - reserved).max(0.0);
+        self.currently_available =
+            (self.currently_available + reserved).min(self.maximum_available);
+    }
+
+    fn sync(&mut self, reserved: f64, status: &ThrottleStatus, now: Instant) {
+        self.in_flight = (self.in_flight - reserved).max(0.0);
+
+        self.maximum_available = status.maximum_available;
+        if status.restore_rate > 0.0 {
+            self.restore_rate = status.restore_rate;
+        }
+
+        // Shopify hasn't seen the other requests that are still in flight yet.
+        self.currently_available = (status.currently_available - self.in_flight).max(0.0);
+        self.updated_at = now;
+    }
+
+    fn remember(&mut self, key: u64, requested_cost: f64) {
+        if self.costs.len() >= MAX_REMEMBERED_COSTS && !self.costs.contains_key(&key) {
+            self.costs.clear();
+        }
+
+        self.costs.insert(key, requested_cost);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(currently_available: f64) -> ThrottleStatus {
+        ThrottleStatus {
+            maximum_available: 1000.0,
+            currently_available,
+            restore_rate: 50.0,
+        }
+    }
+
+    #[test]
+    fn waits_for_the_bucket_to_refill() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(start);
+        bucket.sync(0.0, &status(100.0), start);
+
+        assert_eq!(bucket.try_reserve(80.0, start), Ok(80.0));
+        assert_eq!(bucket.try_reserve(70.0, start), Err(Duration::from_secs(1)));
+        assert_eq!(
+            bucket.try_reserve(70.0, start + Duration::from_secs(1)),
+            Ok(70.0)
+        );
+    }
+
+    #[test]
+    fn accounts_for_requests_in_flight() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(now);
+
+        let first = bucket.try_reserve(100.0, now).unwrap();
+        let second = bucket.try_reserve(100.0, now).unwrap();
+
+        // Shopify has only seen the first request.
+        bucket.sync(first, &status(900.0), now);
+        assert_eq!(bucket.currently_available, 800.0);
+
+        bucket.refund(second);
+        assert_eq!(bucket.currently_available, 900.0);
+        assert_eq!(bucket.in_flight, 0.0);
+    }
+
+    #[test]
+    fn backs_off_until_the_query_fits() {
+        let limiter = RateLimiter::new(RateLimitOptions::default());
+        let cost = QueryCost {
+            requested_query_cost: 500.0,
+            actual_query_cost: None,
+            throttle_status: status(100.0),
+        };
+
+        assert_eq!(limiter.backoff(0, Some(&cost)), Duration::from_secs(8));
+        assert_eq!(limiter.backoff(4, Some(&cost)), Duration::from_secs(16));
+        assert_eq!(limiter.backoff(10, None), Duration::from_secs(30));
+    }
+}
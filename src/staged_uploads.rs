@@ -0,0 +1,222 @@
+use reqwest::{
+    header::CONTENT_TYPE,
+    multipart::{Form, Part},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::utils::{
+    mutation_payload, run_request, GraphQlRequest, ResponseTypes, ShopifyClient, ShopifyGqlError,
+    ShopifyResult,
+};
+
+/// What a staged upload will be used for.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum StagedUploadTargetGenerateUploadResource {
+    /// The JSONL variables file of a bulk mutation.
+    BULK_MUTATION_VARIABLES,
+    COLLECTION_IMAGE,
+    FILE,
+    IMAGE,
+    MODEL_3D,
+    PRODUCT_IMAGE,
+    SHOP_IMAGE,
+    URL_REDIRECT_IMPORT,
+    VIDEO,
+}
+
+/// How the file is sent to the staged upload target.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum StagedUploadHttpMethod {
+    /// A `multipart/form-data` request that sends the target's parameters as
+    /// form fields, followed by the file.
+    POST,
+
+    /// A request whose body is the file itself.
+    PUT,
+}
+
+/// Describes a file to be uploaded.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedUploadInput {
+    resource: StagedUploadTargetGenerateUploadResource,
+    filename: String,
+    mime_type: String,
+    http_method: StagedUploadHttpMethod,
+
+    /// An `UnsignedInt64`, which Shopify expects as a string. Required for
+    /// videos and 3D models.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_size: Option<String>,
+}
+
+impl StagedUploadInput {
+    /// Describes a file that will be uploaded with a `POST` request.
+    pub fn new(
+        resource: StagedUploadTargetGenerateUploadResource,
+        filename: &str,
+        mime_type: &str,
+    ) -> Self {
+        Self {
+            resource,
+            filename: filename.into(),
+            mime_type: mime_type.into(),
+            http_method: StagedUploadHttpMethod::POST,
+            file_size: None,
+        }
+    }
+
+    pub fn http_method(mut self, http_method: StagedUploadHttpMethod) -> Self {
+        self.http_method = http_method;
+        self
+    }
+
+    /// The size of the file in bytes.
+    pub fn file_size(mut self, file_size: u64) -> Self {
+        self.file_size = Some(file_size.to_string());
+        self
+    }
+}
+
+/// A form field or header that has to be sent along with a staged upload.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct StagedUploadParameter {
+    name: String,
+    value: String,
+}
+
+impl StagedUploadParameter {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// Where to upload a file, as returned by `stagedUploadsCreate`.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedUploadTarget {
+    url: String,
+
+    /// The URL to pass to other mutations to refer to the uploaded file.
+    resource_url: Option<String>,
+
+    parameters: Vec<StagedUploadParameter>,
+}
+
+impl StagedUploadTarget {
+    /// The URL that the file is uploaded to.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn resource_url(&self) -> Option<&String> {
+        self.resource_url.as_ref()
+    }
+
+    pub fn parameters(&self) -> &[StagedUploadParameter] {
+        &self.parameters
+    }
+
+    /// Returns the value of the parameter called `name`.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.value.as_str())
+    }
+}
+
+/// Reserves an upload target for `input` with `stagedUploadsCreate`, then
+/// uploads `contents` to it.
+///
+/// Returns the target, whose [`resource_url`](StagedUploadTarget::resource_url)
+/// or `key` parameter refers to the uploaded file in later mutations.
+pub async fn stage_upload(
+    client: &ShopifyClient,
+    input: StagedUploadInput,
+    contents: Vec<u8>,
+) -> ShopifyResult<StagedUploadTarget> {
+    let target = create_target(client, &input).await?;
+
+    // The target is a signed URL, so the request must not carry the access
+    // token.
+    let req = match input.http_method {
+        StagedUploadHttpMethod::POST => {
+            let form = target.parameters.iter().fold(Form::new(), |form, p| {
+                form.text(p.name.clone(), p.value.clone())
+            });
+            let file = Part::bytes(contents)
+                .file_name(input.filename.clone())
+                .mime_str(&input.mime_type)?;
+
+            client
+                .http()
+                .post(&target.url)
+                .multipart(form.part("file", file))
+        }
+
+        StagedUploadHttpMethod::PUT => client
+            .http()
+            .put(&target.url)
+            .header(CONTENT_TYPE, &input.mime_type)
+            .body(contents),
+    };
+
+    req.send().await?.error_for_status()?;
+
+    Ok(target)
+}
+
+async fn create_target(
+    client: &ShopifyClient,
+    input: &StagedUploadInput,
+) -> ShopifyResult<StagedUploadTarget> {
+    let mutation = "mutation stagedUploadsCreate($input: [StagedUploadInput!]!) { stagedUploadsCreate(input: $input) { stagedTargets { url resourceUrl parameters { name value } } userErrors { field message } } }";
+
+    let request = GraphQlRequest::new(mutation).with_variable("input", json!([input]));
+    let res = run_request(client, &request).await?.into_data()?;
+
+    let targets = match res {
+        ResponseTypes::StagedUploadsCreate {
+            staged_targets,
+            user_errors,
+        } => mutation_payload(staged_targets, user_errors)?,
+
+        _ => return Err(ShopifyGqlError::ResponseError(format!("{:?}", res))),
+    };
+
+    targets.into_iter().next().ok_or_else(|| {
+        ShopifyGqlError::ResponseError("stagedUploadsCreate returned no targets".into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_inputs() {
+        let input = StagedUploadInput::new(
+            StagedUploadTargetGenerateUploadResource::BULK_MUTATION_VARIABLES,
+            "variables.jsonl",
+            "text/jsonl",
+        );
+
+        assert_eq!(
+            serde_json::to_value(input).unwrap(),
+            json!({
+                "resource": "BULK_MUTATION_VARIABLES",
+                "filename": "variables.jsonl",
+                "mimeType": "text/jsonl",
+                "httpMethod": "POST"
+            })
+        );
+    }
+}
@@ -3,19 +3,38 @@
 use dotenvy::dotenv;
 use reqwest::{
     header::{HeaderMap, HeaderName, HeaderValue, InvalidHeaderValue, CONTENT_TYPE},
-    Client,
+    Client, StatusCode,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    env::{self, VarError},
+    fmt,
+    sync::Arc,
 };
-use serde::Deserialize;
-use std::env::{self, VarError};
 use thiserror::Error;
 
 use crate::{
     bulk_mutations::BulkOperation,
-    products::{product::Product, product_variant::ProductVariant},
+    collections::Collection,
+    common::{resource, Edges, Id},
+    definitions::{MetafieldDefinition, MetaobjectDefinition},
+    inventory::{InventoryAdjustmentGroup, InventoryItem},
+    locations::Location,
+    metafields::{Metafield, MetafieldIdentifier},
+    metaobjects::Metaobject,
+    products::{media::Media, product::Product, product_variant::ProductVariant},
+    rate_limit::{QueryCost, RateLimitOptions, RateLimiter},
+    staged_uploads::StagedUploadTarget,
 };
 
+/// Errors returned by this crate.
+///
+/// New variants may be added in minor releases, so matches on this type need a
+/// wildcard arm.
 #[derive(Debug, Error)]
-pub(crate) enum ShopifyGqlError {
+#[non_exhaustive]
+pub enum ShopifyGqlError {
     #[error("Environment varible not found")]
     EnvironmentVariableNotFound(#[from] VarError),
 
@@ -31,123 +50,976 @@ pub(crate) enum ShopifyGqlError {
     #[error("Invalid ID ({0}): The ID must only be numbers")]
     InvalidId(String),
 
+    #[error("Invalid global ID ({0})")]
+    InvalidGlobalId(String),
+
+    /// A builder was run in a way its query doesn't support, such as
+    /// streaming a query for a single object.
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
     #[error("Unable to parse {0} as float")]
     FloatParseError(String),
-}
 
-pub(crate) type ShopifyResult<T> = Result<T, ShopifyGqlError>;
+    #[error("Unable to parse {0} as a decimal amount")]
+    InvalidMoney(String),
 
-#[derive(Debug)]
-pub(crate) struct ShopifyConnection {
-    client: Client,
-    headers: HeaderMap,
+    #[error("Unable to parse metafield value {0}")]
+    InvalidMetafieldValue(String),
+
+    #[error("Unable to map metaobject fields: {0}")]
+    InvalidMetaobjectFields(String),
+
+    /// The response contained a top-level `errors` array.
+    ///
+    /// Any data that was returned alongside the errors is kept in `data`.
+    #[error("GraphQL errors: {}", join_errors(.errors))]
+    GraphQlErrors {
+        errors: Vec<GraphQlError>,
+        data: Option<Box<ResponseTypes>>,
+    },
+
+    /// A mutation was rejected and returned `userErrors`.
+    #[error("User errors: {}", join_errors(.0))]
+    UserErrors(Vec<UserError>),
+
+    /// A declared metafield or metaobject field definition has another type
+    /// than the existing one, which can only be changed by deleting the
+    /// definition and creating it again.
+    #[error("The type of definition {key} is {existing}, not {declared}")]
+    DefinitionTypeChanged {
+        key: String,
+        existing: String,
+        declared: String,
+    },
+
+    /// A file can't be staged for upload, such as an external video, which
+    /// is referenced by its URL instead.
+    #[error("Unable to stage upload: {0}")]
+    InvalidUpload(String),
+
+    #[error("Unable to read bulk operation results: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// A bulk operation finished without completing.
+    #[error(
+        "Bulk operation {} finished with status {:?} (error code: {:?})",
+        .0.id().inner(),
+        .0.status(),
+        .0.error_code()
+    )]
+    BulkOperationFailed(BulkOperation),
 }
 
-#[derive(Debug)]
-pub(crate) struct ShopifyConfig {
+fn join_errors<E: fmt::Display>(errors: &[E]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Shorthand for a `Result` with a [`ShopifyGqlError`].
+pub type ShopifyResult<T> = Result<T, ShopifyGqlError>;
+
+/// Credentials and connection details for a Shopify store.
+#[derive(Debug, Clone)]
+pub struct ShopifyConfig {
     /// The API endpoint.
     api_url: String,
 
     /// The API token used for authentication.
     api_token: String,
 
-    connection: Option<ShopifyConnection>,
+    /// How requests are paced to stay under the store's rate limit.
+    rate_limit: RateLimitOptions,
 }
 
 impl ShopifyConfig {
-    pub(crate) fn init(api_url: &str, api_token: &str) -> Self {
+    /// Creates a config for the given Admin GraphQL endpoint and access token.
+    pub fn init(api_url: &str, api_token: &str) -> Self {
+        Self {
+            api_url: api_url.into(),
+            api_token: api_token.into(),
+            rate_limit: RateLimitOptions::default(),
+        }
+    }
+
+    /// Creates a config from the `API_URL` and `API_TOKEN` environment
+    /// variables, loading a `.env` file first if one exists.
+    pub fn from_env() -> ShopifyResult<Self> {
+        dotenv().ok(); // Load vars from .env file
+
+        let api_url = env::var("API_URL")?;
+        let api_token = env::var("API_TOKEN")?;
+
+        Ok(Self::init(&api_url, &api_token))
+    }
+
+    /// Sets how clients created from this config pace their requests.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimitOptions) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// The Admin GraphQL endpoint, such as
+    /// `https://{shop}.myshopify.com/admin/api/2024-04/graphql.json`.
+    ///
+    /// Queries and mutations follow API version 2024-04, which takes a
+    /// variant's SKU and weight under its inventory item, so the URL should
+    /// name that version or a later one.
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    /// Creates a [`ShopifyClient`] that sends requests using this config.
+    pub fn client(&self) -> ShopifyResult<ShopifyClient> {
+        ShopifyClient::new(self)
+    }
+}
+
+#[derive(Debug)]
+struct ShopifyConnection {
+    client: Client,
+    headers: HeaderMap,
+    api_url: String,
+    rate_limiter: RateLimiter,
+}
+
+/// A connection to a Shopify store that the query builders send requests
+/// through.
+///
+/// Cloning a client is cheap and the clones share the same connection pool, so
+/// a single client can be created up front and shared across tasks.
+///
+/// Clones also share a rate limiter that tracks the query costs Shopify
+/// reports, delays requests that would otherwise be throttled, and retries
+/// `THROTTLED` responses with a backoff.
+#[derive(Debug, Clone)]
+pub struct ShopifyClient {
+    connection: Arc<ShopifyConnection>,
+}
+
+impl ShopifyClient {
+    /// Creates a client for the store described by `config`.
+    pub fn new(config: &ShopifyConfig) -> ShopifyResult<Self> {
         // Construct necessary headers to pass to URL
         let headers = {
             let mut headers = HeaderMap::new();
 
-            headers.insert(
-                CONTENT_TYPE,
-                HeaderValue::from_static("application/graphql"),
-            );
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
             headers.insert(
                 HeaderName::from_static("x-shopify-access-token"),
-                HeaderValue::from_str(api_token).expect(
-                    "[HeaderValue Error] The `x-shopify-access-token` header is an invalid string.",
-                ),
+                HeaderValue::from_str(&config.api_token)?,
             );
 
             headers
         };
-        let client = reqwest::Client::new();
-        let connection = Some(ShopifyConnection { client, headers });
 
+        let connection = ShopifyConnection {
+            client: Client::new(),
+            headers,
+            api_url: config.api_url.clone(),
+            rate_limiter: RateLimiter::new(config.rate_limit.clone()),
+        };
+
+        Ok(Self {
+            connection: Arc::new(connection),
+        })
+    }
+
+    /// Creates a client from the `API_URL` and `API_TOKEN` environment
+    /// variables. See [`ShopifyConfig::from_env`].
+    pub fn from_env() -> ShopifyResult<Self> {
+        Self::new(&ShopifyConfig::from_env()?)
+    }
+
+    /// The underlying HTTP client, for requests outside the GraphQL API such
+    /// as downloading bulk operation results.
+    pub(crate) fn http(&self) -> &Client {
+        &self.connection.client
+    }
+}
+
+/// A GraphQL document along with the values of its variables, which is sent
+/// as the JSON body of a request.
+///
+/// User input should always be passed as a variable rather than spliced into
+/// the document, so that it can't change the meaning of the query.
+///
+/// ```
+/// use shopify_gql::utils::GraphQlRequest;
+///
+/// let request = GraphQlRequest::new("query product($id: ID!) { product(id: $id) { title } }")
+///     .with_variable("id", "gid://shopify/Product/1");
+/// ```
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct GraphQlRequest {
+    query: String,
+
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    variables: Map<String, Value>,
+}
+
+impl GraphQlRequest {
+    /// Creates a request for `query` without any variables.
+    pub fn new(query: impl Into<String>) -> Self {
         Self {
-            connection,
-            api_url: api_url.into(),
-            api_token: api_url.into(),
+            query: query.into(),
+            variables: Map::new(),
         }
     }
 
-    pub(crate) fn from_env() -> ShopifyResult<Self> {
-        dotenv().ok(); // Load vars from .env file
+    /// Sets the value of the `$name` variable.
+    pub fn with_variable(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
 
-        let api_url = env::var("API_URL")?;
-        let api_token = env::var("API_TOKEN")?;
+    /// Sets the values of several variables at once.
+    pub fn with_variables(mut self, variables: Map<String, Value>) -> Self {
+        self.variables.extend(variables);
+        self
+    }
 
-        Ok(Self::init(&api_url, &api_token))
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn variables(&self) -> &Map<String, Value> {
+        &self.variables
     }
 }
 
+/// The possible shapes of the `data` field in a response.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub(crate) enum ResponseTypes {
+pub enum ResponseTypes {
     Product(Product),
 
+    Products(Edges<Product>),
+
     ProductVariant(ProductVariant),
 
+    ProductVariants(Edges<ProductVariant>),
+
+    #[serde(rename_all = "camelCase")]
     ProductUpdate {
-        product: Product,
+        product: Option<Product>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductCreate {
+        product: Option<Product>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductDelete {
+        deleted_product_id: Option<Id<resource::Product>>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductDuplicate {
+        new_product: Option<Product>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductChangeStatus {
+        product: Option<Product>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductOptionsCreate {
+        product: Option<Product>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductOptionUpdate {
+        product: Option<Product>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductOptionsDelete {
+        product: Option<Product>,
+        user_errors: Vec<UserError>,
     },
 
     #[serde(rename_all = "camelCase")]
     ProductVariantUpdate {
-        product_variant: ProductVariant,
+        product_variant: Option<ProductVariant>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductVariantsBulkCreate {
+        product_variants: Option<Vec<ProductVariant>>,
+
+        /// Only selected when the variants of a new product are created.
+        product: Option<Product>,
+
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductVariantsBulkUpdate {
+        product_variants: Option<Vec<ProductVariant>>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductVariantsBulkDelete {
+        product: Option<Product>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductVariantsBulkReorder {
+        product: Option<Product>,
+        user_errors: Vec<UserError>,
+    },
+
+    Collection(Collection),
+
+    CollectionByHandle(Option<Collection>),
+
+    Collections(Edges<Collection>),
+
+    #[serde(rename_all = "camelCase")]
+    CollectionCreate {
+        collection: Option<Collection>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    CollectionUpdate {
+        collection: Option<Collection>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    CollectionDelete {
+        deleted_collection_id: Option<Id<resource::Collection>>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    CollectionAddProducts {
+        collection: Option<Collection>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    CollectionRemoveProducts {
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetafieldsSet {
+        metafields: Option<Vec<Metafield>>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetafieldsDelete {
+        deleted_metafields: Option<Vec<Option<MetafieldIdentifier>>>,
+        user_errors: Vec<UserError>,
+    },
+
+    MetafieldDefinition(MetafieldDefinition),
+
+    MetafieldDefinitions(Edges<MetafieldDefinition>),
+
+    #[serde(rename_all = "camelCase")]
+    MetafieldDefinitionCreate {
+        created_definition: Option<MetafieldDefinition>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetafieldDefinitionUpdate {
+        updated_definition: Option<MetafieldDefinition>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetafieldDefinitionDelete {
+        deleted_definition_id: Option<Id<resource::MetafieldDefinition>>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetafieldDefinitionPin {
+        pinned_definition: Option<MetafieldDefinition>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetafieldDefinitionUnpin {
+        unpinned_definition: Option<MetafieldDefinition>,
+        user_errors: Vec<UserError>,
+    },
+
+    MetaobjectDefinition(MetaobjectDefinition),
+
+    MetaobjectDefinitionByType(Option<MetaobjectDefinition>),
+
+    MetaobjectDefinitions(Edges<MetaobjectDefinition>),
+
+    #[serde(rename_all = "camelCase")]
+    MetaobjectDefinitionCreate {
+        metaobject_definition: Option<MetaobjectDefinition>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetaobjectDefinitionUpdate {
+        metaobject_definition: Option<MetaobjectDefinition>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetaobjectDefinitionDelete {
+        deleted_id: Option<Id<resource::MetaobjectDefinition>>,
+        user_errors: Vec<UserError>,
+    },
+
+    Metaobject(Metaobject),
+
+    MetaobjectByHandle(Option<Metaobject>),
+
+    Metaobjects(Edges<Metaobject>),
+
+    #[serde(rename_all = "camelCase")]
+    MetaobjectCreate {
+        metaobject: Option<Metaobject>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetaobjectUpdate {
+        metaobject: Option<Metaobject>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetaobjectUpsert {
+        metaobject: Option<Metaobject>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    MetaobjectDelete {
+        deleted_id: Option<Id<resource::Metaobject>>,
+        user_errors: Vec<UserError>,
+    },
+
+    InventoryItem(InventoryItem),
+
+    Location(Location),
+
+    Locations(Edges<Location>),
+
+    #[serde(rename_all = "camelCase")]
+    InventoryAdjustQuantities {
+        inventory_adjustment_group: Option<InventoryAdjustmentGroup>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    InventorySetOnHandQuantities {
+        inventory_adjustment_group: Option<InventoryAdjustmentGroup>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    InventoryMoveQuantities {
+        inventory_adjustment_group: Option<InventoryAdjustmentGroup>,
+        user_errors: Vec<UserError>,
     },
 
     #[serde(rename_all = "camelCase")]
     BulkOperationRunQuery {
-        bulk_operation: BulkOperation,
+        bulk_operation: Option<BulkOperation>,
+        user_errors: Vec<UserError>,
+    },
+
+    CurrentBulkOperation(Option<BulkOperation>),
+
+    #[serde(rename_all = "camelCase")]
+    BulkOperationRunMutation {
+        bulk_operation: Option<BulkOperation>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductCreateMedia {
+        media: Option<Vec<Media>>,
+        media_user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductUpdateMedia {
+        media: Option<Vec<Media>>,
+        media_user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductDeleteMedia {
+        deleted_media_ids: Option<Vec<Id>>,
+        media_user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductReorderMedia {
+        media_user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    ProductVariantAppendMedia {
+        product_variants: Option<Vec<ProductVariant>>,
+        user_errors: Vec<UserError>,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    StagedUploadsCreate {
+        staged_targets: Option<Vec<StagedUploadTarget>>,
+        user_errors: Vec<UserError>,
     },
 }
 
-#[derive(Debug, Deserialize)]
+/// The position in the query document that an error refers to.
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+pub struct ErrorLocation {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// A segment of the path to the response field that an error refers to.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Field(field) => write!(f, "{}", field),
+            PathSegment::Index(idx) => write!(f, "{}", idx),
+        }
+    }
+}
+
+/// An entry of the top-level `errors` array of a response.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct QueryResponse {
-    pub(crate) data: ResponseTypes,
+pub struct GraphQlError {
+    message: String,
+
+    #[serde(default)]
+    locations: Vec<ErrorLocation>,
+
+    #[serde(default)]
+    path: Vec<PathSegment>,
+
+    #[serde(default)]
+    extensions: Option<Value>,
 }
 
-// TODO: Handle Shopify errors
-pub(crate) async fn run_query(
-    config: ShopifyConfig,
-    query: String,
+impl GraphQlError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn locations(&self) -> &[ErrorLocation] {
+        &self.locations
+    }
+
+    pub fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    /// The error code in `extensions.code`, such as `THROTTLED` or
+    /// `ACCESS_DENIED`.
+    pub fn code(&self) -> Option<&str> {
+        self.extensions.as_ref()?.get("code")?.as_str()
+    }
+
+    /// The raw `extensions` object.
+    pub fn extensions(&self) -> Option<&Value> {
+        self.extensions.as_ref()
+    }
+}
+
+impl fmt::Display for GraphQlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(code) = self.code() {
+            write!(f, "[{}] ", code)?;
+        }
+        write!(f, "{}", self.message)?;
+        if !self.path.is_empty() {
+            let path: Vec<_> = self.path.iter().map(|p| p.to_string()).collect();
+            write!(f, " (at {})", path.join("."))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An error returned by a mutation in its `userErrors` field, such as a
+/// validation failure on one of the inputs.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserError {
+    field: Option<Vec<String>>,
+    message: String,
+
+    /// Only present on mutations whose user errors are typed.
+    #[serde(default)]
+    code: Option<String>,
+}
+
+impl UserError {
+    /// The path to the input field that caused the error.
+    pub fn field(&self) -> Option<&[String]> {
+        self.field.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(code) = &self.code {
+            write!(f, "[{}] ", code)?;
+        }
+        if let Some(field) = &self.field {
+            write!(f, "{}: ", field.join("."))?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Returns the payload of a mutation, or its `userErrors` if there were any.
+pub(crate) fn mutation_payload<T>(
+    payload: Option<T>,
+    user_errors: Vec<UserError>,
+) -> ShopifyResult<T> {
+    if !user_errors.is_empty() {
+        return Err(ShopifyGqlError::UserErrors(user_errors));
+    }
+
+    payload.ok_or_else(|| ShopifyGqlError::ResponseError("The mutation returned no data".into()))
+}
+
+/// A response returned by the Admin GraphQL API.
+#[derive(Debug)]
+pub struct QueryResponse {
+    /// `None` if the request failed before any data could be resolved.
+    pub data: Option<ResponseTypes>,
+
+    pub errors: Vec<GraphQlError>,
+
+    /// The cost of the query, from `extensions.cost`.
+    pub cost: Option<QueryCost>,
+}
+
+impl QueryResponse {
+    /// Returns `true` if Shopify refused to run the query because the rate
+    /// limit was exceeded.
+    pub fn is_throttled(&self) -> bool {
+        self.errors.iter().any(|e| e.code() == Some("THROTTLED"))
+    }
+
+    /// Returns the response data, or a [`ShopifyGqlError::GraphQlErrors`]
+    /// holding any partial data if the response contained errors.
+    pub fn into_data(self) -> ShopifyResult<ResponseTypes> {
+        if !self.errors.is_empty() {
+            return Err(ShopifyGqlError::GraphQlErrors {
+                errors: self.errors,
+                data: self.data.map(Box::new),
+            });
+        }
+
+        self.data
+            .ok_or_else(|| ShopifyGqlError::ResponseError("The response contained no data".into()))
+    }
+}
+
+/// The response as sent by Shopify, before `data` is parsed.
+#[derive(Debug, Deserialize)]
+struct RawResponse {
+    data: Option<Value>,
+
+    #[serde(default, deserialize_with = "deserialize_errors")]
+    errors: Vec<GraphQlError>,
+
+    extensions: Option<RawExtensions>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawExtensions {
+    cost: Option<QueryCost>,
+}
+
+/// Shopify sends `errors` as a plain string for some failures, such as an
+/// invalid access token, so accept that as a single error.
+fn deserialize_errors<'de, D>(deserializer: D) -> Result<Vec<GraphQlError>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Errors {
+        List(Vec<GraphQlError>),
+        Message(String),
+    }
+
+    Ok(match Option::<Errors>::deserialize(deserializer)? {
+        Some(Errors::List(errors)) => errors,
+        Some(Errors::Message(message)) => vec![GraphQlError {
+            message,
+            locations: vec![],
+            path: vec![],
+            extensions: None,
+        }],
+        None => vec![],
+    })
+}
+
+impl TryFrom<RawResponse> for QueryResponse {
+    type Error = ShopifyGqlError;
+
+    fn try_from(raw: RawResponse) -> Result<Self, Self::Error> {
+        let data = match raw.data {
+            None | Some(Value::Null) => None,
+            Some(data) => match serde_json::from_value(data) {
+                Ok(data) => Some(data),
+
+                // Fields that failed to resolve come back as `null`, so
+                // partial data may not fit `ResponseTypes`; the errors are
+                // more useful than the parse failure in that case.
+                Err(_) if !raw.errors.is_empty() => None,
+
+                Err(e) => {
+                    return Err(ShopifyGqlError::ResponseError(format!(
+                        "Unable to parse response: {}",
+                        e
+                    )))
+                }
+            },
+        };
+
+        Ok(QueryResponse {
+            data,
+            errors: raw.errors,
+            cost: raw.extensions.and_then(|e| e.cost),
+        })
+    }
+}
+
+/// Sends a raw GraphQL document through `client`.
+///
+/// Errors in the response are returned as part of the [`QueryResponse`]; use
+/// [`QueryResponse::into_data`] to turn them into a [`ShopifyGqlError`].
+///
+/// Requests wait for the client's rate limiter before they are sent, and
+/// throttled requests are retried according to its [`RateLimitOptions`].
+pub async fn run_query(client: &ShopifyClient, query: String) -> ShopifyResult<QueryResponse> {
+    run_request(client, &GraphQlRequest::new(query)).await
+}
+
+/// Sends a GraphQL document and its variables through `client`.
+///
+/// Behaves like [`run_query`] otherwise.
+pub async fn run_request(
+    client: &ShopifyClient,
+    request: &GraphQlRequest,
 ) -> ShopifyResult<QueryResponse> {
-    let conn = match config.connection {
-        Some(conn) => conn,
-        None => unreachable!(),
-    };
-    let client = conn.client;
-    let headers = conn.headers;
-    let url = config.api_url;
-
-    let res = client.post(url).headers(headers).body(query).send().await?;
-    // let t: serde_json::Value = res.json().await?;
-    // dbg!(t);
-    // todo!()
-
-    let ret: Result<QueryResponse, _> = res.json().await;
-    let ret = match ret {
-        Ok(r) => Ok(r),
+    let conn = &client.connection;
+    let limiter = &conn.rate_limiter;
+
+    let mut attempt = 0;
+    loop {
+        // The cost depends on the document rather than on the variables.
+        let reservation = limiter.acquire(&request.query).await;
+        let res = match send_query(conn, request).await {
+            Ok(res) => res,
+            Err(e) => {
+                limiter.complete(reservation, None);
+                return Err(e);
+            }
+        };
+        limiter.complete(reservation, res.cost.as_ref());
+
+        if res.is_throttled() && attempt < limiter.options().max_retries {
+            tokio::time::sleep(limiter.backoff(attempt, res.cost.as_ref())).await;
+            attempt += 1;
+            continue;
+        }
+
+        return Ok(res);
+    }
+}
+
+async fn send_query(
+    conn: &ShopifyConnection,
+    request: &GraphQlRequest,
+) -> ShopifyResult<QueryResponse> {
+    let res = conn
+        .client
+        .post(&conn.api_url)
+        .headers(conn.headers.clone())
+        .json(request)
+        .send()
+        .await?;
+
+    // The GraphQL API normally reports throttling in `errors`, but treat an
+    // HTTP 429 the same way.
+    if res.status() == StatusCode::TOO_MANY_REQUESTS {
+        return Ok(QueryResponse {
+            data: None,
+            errors: vec![GraphQlError {
+                message: "Too many requests".into(),
+                locations: vec![],
+                path: vec![],
+                extensions: Some(serde_json::json!({ "code": "THROTTLED" })),
+            }],
+            cost: None,
+        });
+    }
+
+    let ret: Result<RawResponse, _> = res.json().await;
+    match ret {
+        Ok(r) => r.try_into(),
         Err(e) => Err(ShopifyGqlError::ResponseError(format!(
             "Unable to parse response: {}",
             e
         ))),
-    };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    ret
+    fn parse(json: &str) -> ShopifyResult<QueryResponse> {
+        serde_json::from_str::<RawResponse>(json)
+            .unwrap()
+            .try_into()
+    }
+
+    #[test]
+    fn keeps_partial_data_with_errors() {
+        let res = parse(
+            r#"{
+                "data": { "product": { "id": "gid://shopify/Product/1", "title": null } },
+                "errors": [{
+                    "message": "Access denied for title field.",
+                    "locations": [{ "line": 1, "column": 50 }],
+                    "path": ["product", "title"],
+                    "extensions": { "code": "ACCESS_DENIED" }
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        match res.into_data().unwrap_err() {
+            ShopifyGqlError::GraphQlErrors { errors, data } => {
+                assert_eq!(errors[0].code(), Some("ACCESS_DENIED"));
+                assert_eq!(errors[0].locations()[0].column, 50);
+                assert_eq!(
+                    errors[0].to_string(),
+                    "[ACCESS_DENIED] Access denied for title field. (at product.title)"
+                );
+                assert!(matches!(data.as_deref(), Some(ResponseTypes::Product(_))));
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn accepts_string_errors() {
+        let res = parse(r#"{ "errors": "[API] Invalid API key or access token" }"#).unwrap();
+
+        assert!(res.data.is_none());
+        assert_eq!(
+            res.errors[0].message(),
+            "[API] Invalid API key or access token"
+        );
+    }
+
+    #[test]
+    fn returns_user_errors() {
+        let data = parse(
+            r#"{
+                "data": { "productUpdate": {
+                    "product": null,
+                    "userErrors": [{ "field": ["title"], "message": "Title can't be blank" }]
+                } }
+            }"#,
+        )
+        .unwrap()
+        .into_data()
+        .unwrap();
+
+        let err = match data {
+            ResponseTypes::ProductUpdate {
+                product,
+                user_errors,
+            } => mutation_payload(product, user_errors).unwrap_err(),
+            _ => unreachable!(),
+        };
+        assert_eq!(err.to_string(), "User errors: title: Title can't be blank");
+    }
+
+    #[test]
+    fn sends_user_input_as_variables() {
+        let request =
+            GraphQlRequest::new("mutation productUpdate($input: ProductInput!) { productUpdate(input: $input) { product { id } } }")
+                .with_variable("input", serde_json::json!({ "title": "A \"quoted\" title" }));
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "query": request.query(),
+                "variables": { "input": { "title": "A \"quoted\" title" } }
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(GraphQlRequest::new("{ shop { name } }")).unwrap(),
+            serde_json::json!({ "query": "{ shop { name } }" })
+        );
+    }
+
+    #[test]
+    fn client_can_be_shared_across_tasks() {
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<ShopifyClient>();
+    }
+
+    #[test]
+    fn rejects_invalid_token() {
+        let config = ShopifyConfig::init("https://example.myshopify.com", "bad\ntoken");
+
+        assert!(matches!(
+            config.client(),
+            Err(ShopifyGqlError::InvalidApiToken(_))
+        ));
+    }
 }